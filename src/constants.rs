@@ -54,6 +54,9 @@ pub enum IndexTag {
 
     RPMTAG_SHA256HEADER = RPMTAG_SIG_BASE + 17,
 
+    RPMTAG_VERITYSIGNATURES = RPMTAG_SIG_BASE + 20,
+    RPMTAG_VERITYSIGNATUREALGO = RPMTAG_SIG_BASE + 21,
+
     RPMTAG_NAME = 1000,
 
     RPMTAG_VERSION = 1001,
@@ -409,6 +412,14 @@ pub enum IndexSignatureTag {
     // If this tag is present, then the SIGTAG_FILESIGNATURE shall also be present.
     RPMSIGTAG_FILESIGNATURE_LENGTH = 275,
 
+    // The tag contains the base64 encoded PKCS#7 fs-verity signature of each file,
+    // an empty string for files that are not regular files.
+    // If this tag is present, then the SIGTAG_VERITYSIGNATUREALGO shall also be present.
+    RPMSIGTAG_VERITYSIGNATURES = IndexTag::RPMTAG_VERITYSIGNATURES as isize,
+
+    // The tag contains the hash algorithm of the fs-verity Merkle trees, 1 for SHA-256.
+    RPMSIGTAG_VERITYSIGNATUREALGO = IndexTag::RPMTAG_VERITYSIGNATUREALGO as isize,
+
     // This  tag  specifies  the  RSA  signature  of  the  combined  Header  and  Payload  sections.
     // The data is formatted as a Version 3 Signature Packet as specified in RFC 2440: OpenPGP Message Format.
     RPMSIGTAG_PGP = 1002,
@@ -421,8 +432,9 @@ pub enum IndexSignatureTag {
     //including the Header Record, Index Records and Header store.
     RPMSIGTAG_SHA256 = IndexTag::RPMTAG_SHA256HEADER as isize,
 
-    // A silly tag for a date.
-    RPMTAG_INSTALLTIME = IndexTag::RPMTAG_INSTALLTIME as isize,
+    // Zeroed space reserved for signatures, so signing
+    // can happen in place without moving header and payload.
+    RPMSIGTAG_RESERVEDSPACE = HEADER_TAGBASE + 8,
 }
 
 pub trait TypeName {
@@ -449,47 +461,63 @@ pub const RPM_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
 /// header magic recognition (not the lead!)
 pub const HEADER_MAGIC: [u8; 3] = [0x8e, 0xad, 0xe8];
 
+/// space reserved for signatures in unsigned packages, the `%__gpg_reserved_space` of rpmbuild
+pub const SIGNATURE_RESERVED_SPACE: usize = 4096;
+
 pub const RPMSENSE_ANY: u32 = 0;
 pub const RPMSENSE_LESS: u32 = 1 << 1;
 pub const RPMSENSE_GREATER: u32 = 1 << 2;
 pub const RPMSENSE_EQUAL: u32 = 1 << 3;
 
+pub const RPMSENSE_POSTTRANS: u32 = 1 << 5;
+/// Set by the legacy `PreReq:` tag.
+pub const RPMSENSE_PREREQ: u32 = 1 << 6;
+pub const RPMSENSE_PRETRANS: u32 = 1 << 7;
+pub const RPMSENSE_INTERP: u32 = 1 << 8;
+pub const RPMSENSE_SCRIPT_PRE: u32 = 1 << 9;
+pub const RPMSENSE_SCRIPT_POST: u32 = 1 << 10;
+pub const RPMSENSE_SCRIPT_PREUN: u32 = 1 << 11;
+pub const RPMSENSE_SCRIPT_POSTUN: u32 = 1 << 12;
+pub const RPMSENSE_SCRIPT_VERIFY: u32 = 1 << 13;
+/// Generated from the package files.
+pub const RPMSENSE_FIND_REQUIRES: u32 = 1 << 14;
+/// Generated from the package files.
+pub const RPMSENSE_FIND_PROVIDES: u32 = 1 << 15;
+pub const RPMSENSE_TRIGGERIN: u32 = 1 << 16;
+pub const RPMSENSE_TRIGGERUN: u32 = 1 << 17;
+pub const RPMSENSE_TRIGGERPOSTUN: u32 = 1 << 18;
+pub const RPMSENSE_RPMLIB: u32 = 1 << 24;
+pub const RPMSENSE_TRIGGERPREIN: u32 = 1 << 25;
+
 // there is no use yet for those constants. But they are part of the official package
 // so I will leave them in in case we need them later.
 
-// const RPMSENSE_POSTTRANS: u32 = (1 << 5);
-// const RPMSENSE_PREREQ: u32 = (1 << 6);
-// const RPMSENSE_PRETRANS: u32 = (1 << 7);
-// const RPMSENSE_INTERP: u32 = (1 << 8);
-// const RPMSENSE_SCRIPT_PRE: u32 = (1 << 9);
-// const RPMSENSE_SCRIPT_POST: u32 = (1 << 10);
-// const RPMSENSE_SCRIPT_PREUN: u32 = (1 << 11);
-// const RPMSENSE_SCRIPT_POSTUN: u32 = (1 << 12);
-// const RPMSENSE_SCRIPT_VERIFY: u32 = (1 << 13);
-// const RPMSENSE_FIND_REQUIRES: u32 = (1 << 14);
-// const RPMSENSE_FIND_PROVIDES: u32 = (1 << 15);
-// const RPMSENSE_TRIGGERIN: u32 = (1 << 16);
-// const RPMSENSE_TRIGGERUN: u32 = (1 << 17);
-// const RPMSENSE_TRIGGERPOSTUN: u32 = (1 << 18);
 // const RPMSENSE_MISSINGOK: u32 = (1 << 19);
 
-// // for some weird reason, centos packages have another value for rpm lib sense. We have to observe this.
-// const RPMSENSE_RPMLIB: u32 = (1 << 24); //0o100000012;
-// const RPMSENSE_TRIGGERPREIN: u32 = (1 << 25);
 // const RPMSENSE_KEYRING: u32 = (1 << 26);
 // const RPMSENSE_CONFIG: u32 = (1 << 28);
 
+// copied from rpmscript.h
+pub const RPMSCRIPT_FLAG_EXPAND: u32 = 1;
+pub const RPMSCRIPT_FLAG_QFORMAT: u32 = 1 << 1;
+pub const RPMSCRIPT_FLAG_CRITICAL: u32 = 1 << 2;
+
 pub const RPMFILE_CONFIG: i32 = 1;
 pub const RPMFILE_DOC: i32 = 1 << 1;
+pub const RPMFILE_SPECFILE: i32 = 1 << 5;
 // const RPMFILE_DONOTUSE: i32 = (1 << 2);
 // const RPMFILE_MISSINGOK: i32 = (1 << 3);
 // const RPMFILE_NOREPLACE: i32 = (1 << 4);
-// const RPMFILE_SPECFILE: i32 = (1 << 5);
 // const RPMFILE_GHOST: i32 = (1 << 6);
 // const RPMFILE_LICENSE: i32 = (1 << 7);
 // const RPMFILE_README: i32 = (1 << 8);
 // const RPMFILE_EXCLUDE: i32 = (1 << 9);
 
+// copied from rpmfc.h
+pub const RPMFC_BLACK: u32 = 0;
+pub const RPMFC_ELF32: u32 = 1;
+pub const RPMFC_ELF64: u32 = 2;
+
 // copied from rpmpgp.h
 // should be technically equiv to
 // `pgp::crypto::hash::HashAlgorithm`
@@ -504,3 +532,6 @@ pub const PGPHASHALGO_SHA256: i32 = 8;
 pub const PGPHASHALGO_SHA384: i32 = 9;
 pub const PGPHASHALGO_SHA512: i32 = 10;
 pub const PGPHASHALGO_SHA224: i32 = 11;
+
+// copied from linux/fsverity.h
+pub const FS_VERITY_HASH_ALG_SHA256: i32 = 1;
@@ -46,6 +46,13 @@ pub enum RPMError {
     #[error("invalid tag array index {tag} with {index} while bounded at {bound}")]
     InvalidTagIndex { tag: String, index: u32, bound: u32 },
 
+    #[error("tag {tag} has {actual} entries, expected one per file ({expected})")]
+    InvalidTagArrayLength {
+        tag: String,
+        expected: usize,
+        actual: usize,
+    },
+
     #[error("invalid tag value enum varaint for {tag} with {variant}")]
     InvalidTagValueEnumVariant { tag: String, variant: u32 },
 
@@ -67,6 +74,24 @@ pub enum RPMError {
     #[error("invalid size of reserved area - expected length of {expected} but got {actual}")]
     InvalidReservedSpaceSize { expected: u16, actual: usize },
 
+    #[error("invalid dependency {expression:?} - {reason}")]
+    InvalidDependency {
+        expression: String,
+        reason: &'static str,
+    },
+
+    #[error("invalid SOURCE_DATE_EPOCH {0:?} - expected seconds since the epoch")]
+    InvalidSourceDateEpoch(String),
+
+    #[error("{0} can only be added to a source package")]
+    SourcePackageOnly(&'static str),
+
+    #[error("{0} can not be added to a source package")]
+    BinaryPackageOnly(&'static str),
+
+    #[error("duplicate source or patch file {0}")]
+    DuplicateSourceFile(String),
+
     #[error("invalid destination path {path} - {desc}")]
     InvalidDestinationPath { path: String, desc: &'static str },
 
@@ -76,6 +101,15 @@ pub enum RPMError {
     #[error("error creating signature: {0}")]
     SignError(Box<dyn std::error::Error>),
 
+    #[error("wrong or missing passphrase for the secret key")]
+    WrongPassphrase,
+
+    #[error("unsupported key algorithm {0}")]
+    UnsupportedKeyAlgorithm(String),
+
+    #[error("unsupported secret key protection - {0}")]
+    UnsupportedKeyProtection(&'static str),
+
     #[error("error parsing key - {details}. underlying error was: {source}")]
     KeyLoadError {
         source: Box<dyn std::error::Error>,
@@ -88,6 +122,21 @@ pub enum RPMError {
         key_ref: String,
     },
 
+    #[error("invalid detached signature - {0}")]
+    InvalidDetachedSignature(&'static str),
+
+    #[error("invalid IMA signature - {0}")]
+    InvalidImaSignature(&'static str),
+
+    #[error("invalid fs-verity signature - {0}")]
+    InvalidVeritySignature(&'static str),
+
+    #[error("file signature of {path} does not verify: {source}")]
+    FileSignatureError { path: String, source: Box<RPMError> },
+
+    #[error("payload digest mismatch - the payload does not match the digest in the header")]
+    PayloadDigestMismatch,
+
     #[error("unable to find key with key-ref: {key_ref}")]
     KeyNotFoundError { key_ref: String },
 
@@ -97,8 +146,21 @@ pub enum RPMError {
     #[error("unsupported file digest algorithm {0:?}")]
     UnsupportedFileDigestAlgorithm(FileDigestAlgorithm),
 
+    #[error("invalid {algorithm:?} file digest of {actual} bytes, expected {expected}")]
+    InvalidFileDigestLength {
+        algorithm: FileDigestAlgorithm,
+        expected: usize,
+        actual: usize,
+    },
+
     #[error("invalid file mode {raw_mode} - {reason}")]
     InvalidFileMode { raw_mode: i32, reason: &'static str },
+
+    #[error("invalid extended attribute entry {0:?} - expected name=value")]
+    InvalidXattrEntry(String),
+
+    #[error("invalid trigger - {0}")]
+    InvalidTrigger(&'static str),
 }
 
 impl From<nom::Err<(&[u8], nom::error::ErrorKind)>> for RPMError {
@@ -45,6 +45,25 @@ async fn tokio_file_mode(_file: &tokio::fs::File) -> Result<u32, RPMError> {
     Ok(0)
 }
 
+// cpio newc entries start with a fixed size header, the stripped format with the magic and file index
+const CPIO_NEWC_HEADER_LEN: u64 = 110;
+const CPIO_TRAILER_NAME: &str = "TRAILER!!!";
+pub(crate) const CPIO_STRIPPED_MAGIC: &str = "07070X";
+
+/// cpio pads headers and contents to a multiple of 4 bytes.
+pub(crate) fn cpio_padding(len: u64) -> usize {
+    ((4 - len % 4) % 4) as usize
+}
+
+/// The file signatures of a package, which go into the signature header.
+#[derive(Default)]
+struct FileSignatures {
+    /// Hex encoded IMA signatures.
+    ima: Vec<String>,
+    /// Base64 encoded fs-verity signatures.
+    verity: Vec<String>,
+}
+
 /// Builder pattern for a full rpm file.
 ///
 /// Prefered method of creating a rpm file.
@@ -58,6 +77,31 @@ pub struct RPMBuilder {
     gid: Option<u32>,
     desc: String,
     release: String,
+    summary: Option<String>,
+    group: Option<String>,
+    url: Option<String>,
+    vendor: Option<String>,
+    packager: Option<String>,
+    distribution: Option<String>,
+    bug_url: Option<String>,
+    vcs: Option<String>,
+    build_host: Option<String>,
+    source_rpm: Option<String>,
+    dist_tag: Option<String>,
+    modularity_label: Option<String>,
+    build_time: Option<i32>,
+    source_date: Option<i32>,
+
+    prefixes: Vec<String>,
+    #[cfg(feature = "dependency-generator")]
+    generate_dependencies: bool,
+
+    source_package: bool,
+    sources: Vec<String>,
+    no_sources: Vec<i32>,
+    patches: Vec<String>,
+    no_patches: Vec<i32>,
+    build_requires: Vec<Dependency>,
 
     // File entries need to be sorted. The entries need to be in the same order as they come
     // in the cpio payload. Otherwise rpm will not be able to resolve those paths.
@@ -66,18 +110,29 @@ pub struct RPMBuilder {
     directories: BTreeSet<String>,
     requires: Vec<Dependency>,
     obsoletes: Vec<Dependency>,
+    recommends: Vec<Dependency>,
+    suggests: Vec<Dependency>,
+    supplements: Vec<Dependency>,
+    enhances: Vec<Dependency>,
     provides: Vec<Dependency>,
     conflicts: Vec<Dependency>,
 
-    pre_inst_script: Option<String>,
-    post_inst_script: Option<String>,
-    pre_uninst_script: Option<String>,
-    post_uninst_script: Option<String>,
+    scriptlets: BTreeMap<ScriptletType, Scriptlet>,
+    triggers: Vec<Trigger>,
+    file_triggers: Vec<FileTrigger>,
 
     changelog_authors: Vec<String>,
     changelog_entries: Vec<String>,
     changelog_times: Vec<i32>,
     compressor: Compressor,
+    file_digest_algorithm: FileDigestAlgorithm,
+    #[cfg(feature = "signature-ima")]
+    file_signer: Option<signature::ima::ImaSigner>,
+    #[cfg(feature = "signature-verity")]
+    verity_signer: Option<signature::verity::VeritySigner>,
+    /// Files larger than this need 64 bit sizes and the stripped cpio format,
+    /// lowered by the tests to cover them without writing 4 GiB.
+    pub(crate) large_file_threshold: u64,
 }
 
 impl RPMBuilder {
@@ -90,21 +145,53 @@ impl RPMBuilder {
             arch: arch.to_string(),
             desc: desc.to_string(),
             release: "1".to_string(),
+            summary: None,
+            group: None,
+            url: None,
+            vendor: None,
+            packager: None,
+            distribution: None,
+            bug_url: None,
+            vcs: None,
+            build_host: None,
+            source_rpm: None,
+            dist_tag: None,
+            modularity_label: None,
+            build_time: None,
+            source_date: None,
+            prefixes: Vec::new(),
+            #[cfg(feature = "dependency-generator")]
+            generate_dependencies: false,
+            source_package: false,
+            sources: Vec::new(),
+            no_sources: Vec::new(),
+            patches: Vec::new(),
+            no_patches: Vec::new(),
+            build_requires: Vec::new(),
             uid: None,
             gid: None,
             conflicts: Vec::new(),
             provides: Vec::new(),
             obsoletes: Vec::new(),
+            recommends: Vec::new(),
+            suggests: Vec::new(),
+            supplements: Vec::new(),
+            enhances: Vec::new(),
             requires: Vec::new(),
-            pre_inst_script: None,
-            post_inst_script: None,
-            pre_uninst_script: None,
-            post_uninst_script: None,
+            scriptlets: BTreeMap::new(),
+            triggers: Vec::new(),
+            file_triggers: Vec::new(),
             files: BTreeMap::new(),
             changelog_authors: Vec::new(),
             changelog_entries: Vec::new(),
             changelog_times: Vec::new(),
             compressor: Compressor::None(Vec::new()),
+            file_digest_algorithm: FileDigestAlgorithm::Sha2_256,
+            #[cfg(feature = "signature-ima")]
+            file_signer: None,
+            #[cfg(feature = "signature-verity")]
+            verity_signer: None,
+            large_file_threshold: u32::MAX as u64,
             directories: BTreeSet::new(),
         }
     }
@@ -114,11 +201,145 @@ impl RPMBuilder {
         self
     }
 
+    /// A one line summary, defaults to the description.
+    pub fn summary<T: Into<String>>(mut self, summary: T) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// The package group, defaults to `Unspecified`.
+    pub fn group<T: Into<String>>(mut self, group: T) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// The upstream project URL.
+    pub fn url<T: Into<String>>(mut self, url: T) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// The organization distributing the package.
+    pub fn vendor<T: Into<String>>(mut self, vendor: T) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    /// Who built the package, usually `Name <email>`.
+    pub fn packager<T: Into<String>>(mut self, packager: T) -> Self {
+        self.packager = Some(packager.into());
+        self
+    }
+
+    /// The distribution the package is part of.
+    pub fn distribution<T: Into<String>>(mut self, distribution: T) -> Self {
+        self.distribution = Some(distribution.into());
+        self
+    }
+
+    /// Where to report bugs.
+    pub fn bug_url<T: Into<String>>(mut self, bug_url: T) -> Self {
+        self.bug_url = Some(bug_url.into());
+        self
+    }
+
+    /// The version control location of the sources, i.e. `git+https://example.com/repo#commit`.
+    pub fn vcs<T: Into<String>>(mut self, vcs: T) -> Self {
+        self.vcs = Some(vcs.into());
+        self
+    }
+
+    /// The host the package was built on.
+    pub fn build_host<T: Into<String>>(mut self, build_host: T) -> Self {
+        self.build_host = Some(build_host.into());
+        self
+    }
+
+    /// The name of the source package, i.e. `foo-1.0-1.src.rpm`.
+    pub fn source_rpm<T: Into<String>>(mut self, source_rpm: T) -> Self {
+        self.source_rpm = Some(source_rpm.into());
+        self
+    }
+
+    /// The distribution tag, i.e. `el9`.
+    pub fn dist_tag<T: Into<String>>(mut self, dist_tag: T) -> Self {
+        self.dist_tag = Some(dist_tag.into());
+        self
+    }
+
+    /// The modularity label `name:stream:version:context`.
+    pub fn modularity_label<T: Into<String>>(mut self, modularity_label: T) -> Self {
+        self.modularity_label = Some(modularity_label.into());
+        self
+    }
+
+    /// The build timestamp in seconds since the epoch.
+    pub fn build_time(mut self, build_time: i32) -> Self {
+        self.build_time = Some(build_time);
+        self
+    }
+
+    /// Build reproducibly as of `timestamp`, usually `SOURCE_DATE_EPOCH`.
+    ///
+    /// File modification times later than `timestamp` are clamped to it and it is
+    /// used as build time unless one is set explicitly. Use
+    /// [`Signer::with_signing_time`](crate::signature::pgp::Signer::with_signing_time)
+    /// for reproducible signatures.
+    pub fn source_date(mut self, timestamp: i32) -> Self {
+        self.source_date = Some(timestamp);
+        self
+    }
+
+    /// Call [`source_date`](Self::source_date) with `SOURCE_DATE_EPOCH` from the environment, if set.
+    pub fn source_date_from_env(self) -> Result<Self, RPMError> {
+        match std::env::var("SOURCE_DATE_EPOCH") {
+            Ok(raw) => {
+                let timestamp = raw
+                    .trim()
+                    .parse()
+                    .map_err(|_| RPMError::InvalidSourceDateEpoch(raw.clone()))?;
+                Ok(self.source_date(timestamp))
+            }
+            Err(_) => Ok(self),
+        }
+    }
+
     pub fn compression(mut self, comp: Compressor) -> Self {
         self.compressor = comp;
         self
     }
 
+    /// The algorithm of the file digests, SHA-256 by default.
+    ///
+    /// MD5, SHA-1 and the SHA-2 family are supported, MD5 is understood by
+    /// any rpm version while the others require rpm 4.6 or later.
+    pub fn file_digest_algorithm(mut self, algorithm: FileDigestAlgorithm) -> Self {
+        self.file_digest_algorithm = algorithm;
+        self
+    }
+
+    /// Sign the digest of each file with an IMA key, which rpm installs as
+    /// `security.ima` extended attribute, like `rpmsign --signfiles` does.
+    ///
+    /// The signatures are stored in `RPMSIGTAG_FILESIGNATURES` and checked
+    /// with [`RPMPackage::verify_file_signatures`].
+    #[cfg(feature = "signature-ima")]
+    pub fn sign_files(mut self, signer: signature::ima::ImaSigner) -> Self {
+        self.file_signer = Some(signer);
+        self
+    }
+
+    /// Sign the fs-verity digest of each regular file, which rpm's fsverity
+    /// plugin enables fs-verity with, like `rpmsign --signverity` does.
+    ///
+    /// The signatures are stored in `RPMSIGTAG_VERITYSIGNATURES` and checked
+    /// with [`RPMPackage::verify_verity_signatures`].
+    #[cfg(feature = "signature-verity")]
+    pub fn sign_files_verity(mut self, signer: signature::verity::VeritySigner) -> Self {
+        self.verity_signer = Some(signer);
+        self
+    }
+
     pub fn add_changelog_entry<E, F>(mut self, author: E, entry: F, time: i32) -> Self
     where
         E: Into<String>,
@@ -188,9 +409,7 @@ impl RPMBuilder {
         modified_at: i32,
         options: RPMFileOptions,
     ) -> Result<(), RPMError> {
-        use sha2::Digest;
-
-        let dest = options.destination;
+        let dest = options.destination.clone();
         if !dest.starts_with("./") && !dest.starts_with('/') {
             return Err(RPMError::InvalidDestinationPath {
                 path: dest,
@@ -218,13 +437,23 @@ impl RPMBuilder {
             )
         };
 
-        let mut hasher = sha2::Sha256::default();
-        hasher.update(&content);
-        let hash_result = hasher.finalize();
-        let sha_checksum = hex::encode(hash_result); // encode as string
+        let base_name = pb.file_name().unwrap().to_string_lossy().to_string();
+        self.insert_entry(cpio_path, dir, base_name, content, modified_at, options);
+        Ok(())
+    }
+
+    fn insert_entry(
+        &mut self,
+        cpio_path: String,
+        dir: String,
+        base_name: String,
+        content: Vec<u8>,
+        modified_at: i32,
+        options: RPMFileOptions,
+    ) {
         let entry = RPMFileEntry {
-            base_name: pb.file_name().unwrap().to_string_lossy().to_string(),
-            size: content.len() as i32,
+            base_name,
+            size: content.len() as u64,
             content: Some(content),
             flag: options.flag,
             user: options.user,
@@ -233,31 +462,163 @@ impl RPMBuilder {
             link: options.symlink,
             modified_at,
             dir: dir.clone(),
-            sha_checksum,
+            selinux_context: options.selinux_context,
+            xattrs: options.xattrs,
+            caps: options.caps,
         };
 
         self.directories.insert(dir);
         self.files.entry(cpio_path).or_insert(entry);
-        Ok(())
     }
 
-    pub fn pre_install_script<T: Into<String>>(mut self, content: T) -> Self {
-        self.pre_inst_script = Some(content.into());
+    /// Add a file with a flat path, as source packages store them.
+    fn add_source_file(&mut self, source: &Path, flag: i32) -> Result<String, RPMError> {
+        let name = source
+            .file_name()
+            .ok_or_else(|| RPMError::InvalidDestinationPath {
+                path: source.to_string_lossy().to_string(),
+                desc: "no file name found",
+            })?
+            .to_string_lossy()
+            .to_string();
+        if self.files.contains_key(&name) {
+            return Err(RPMError::DuplicateSourceFile(name));
+        }
+        let mut input = std::fs::File::open(source)?;
+        let mut content = Vec::new();
+        input.read_to_end(&mut content)?;
+        let modified_at = input
+            .metadata()?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .expect("something really wrong with your time")
+            .as_secs() as i32;
+        let mut options: RPMFileOptions = RPMFileOptions::new(name.as_str()).mode(0o100_644).into();
+        options.flag = flag;
+        self.insert_entry(
+            name.clone(),
+            "".to_string(),
+            name.clone(),
+            content,
+            modified_at,
+            options,
+        );
+        Ok(name)
+    }
+
+    /// Make the package relocatable below `prefix` with `rpm --prefix`.
+    ///
+    /// All files must be below one of the prefixes.
+    pub fn prefix<T: Into<String>>(mut self, prefix: T) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Generate requires and provides from the ELF objects and executable scripts in the package.
+    ///
+    /// Like rpmbuild, shared objects provide their soname and symbol versions, ELF objects
+    /// require the sonames and symbol versions they link against and scripts require their
+    /// interpreter.
+    #[cfg(feature = "dependency-generator")]
+    pub fn generate_dependencies(mut self) -> Self {
+        self.generate_dependencies = true;
+        self
+    }
+
+    /// Build a source package (SRPM) instead of a binary package.
+    ///
+    /// Use [`with_spec_file`](Self::with_spec_file), [`with_source`](Self::with_source)
+    /// and [`with_patch`](Self::with_patch) to add its files.
+    pub fn source_package(mut self) -> Self {
+        self.source_package = true;
         self
     }
 
-    pub fn post_install_script<T: Into<String>>(mut self, content: T) -> Self {
-        self.post_inst_script = Some(content.into());
+    /// Add the spec file of a source package.
+    pub fn with_spec_file<P: AsRef<Path>>(mut self, spec: P) -> Result<Self, RPMError> {
+        self.add_source_file(spec.as_ref(), RPMFILE_SPECFILE)?;
+        Ok(self)
+    }
+
+    /// Add the next source file, `Source0` first.
+    pub fn with_source<P: AsRef<Path>>(mut self, source: P) -> Result<Self, RPMError> {
+        let name = self.add_source_file(source.as_ref(), 0)?;
+        self.sources.push(name);
+        Ok(self)
+    }
+
+    /// List the next source file without including it, i.e. for non-redistributable sources.
+    pub fn no_source<T: Into<String>>(mut self, name: T) -> Self {
+        self.no_sources.push(self.sources.len() as i32);
+        self.sources.push(name.into());
+        self
+    }
+
+    /// Add the next patch file, `Patch0` first.
+    pub fn with_patch<P: AsRef<Path>>(mut self, patch: P) -> Result<Self, RPMError> {
+        let name = self.add_source_file(patch.as_ref(), 0)?;
+        self.patches.push(name);
+        Ok(self)
+    }
+
+    /// List the next patch file without including it.
+    pub fn no_patch<T: Into<String>>(mut self, name: T) -> Self {
+        self.no_patches.push(self.patches.len() as i32);
+        self.patches.push(name.into());
+        self
+    }
+
+    /// A dependency needed to build the binary packages of a source package.
+    pub fn build_requires<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.build_requires.push(dep.into());
+        self
+    }
+
+    pub fn pre_trans_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::PreTransaction, content)
+    }
+
+    pub fn pre_install_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::PreInstall, content)
+    }
+
+    pub fn post_install_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::PostInstall, content)
+    }
+
+    pub fn pre_uninstall_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::PreUninstall, content)
+    }
+
+    pub fn post_uninstall_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::PostUninstall, content)
+    }
+
+    pub fn post_trans_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::PostTransaction, content)
+    }
+
+    pub fn verify_script<T: Into<Scriptlet>>(self, content: T) -> Self {
+        self.scriptlet(ScriptletType::Verify, content)
+    }
+
+    /// Set the scriptlet of the given type, replacing a previously set one.
+    ///
+    /// Plain strings are run with `/bin/sh`, use [`Scriptlet::prog`] to pick another interpreter.
+    pub fn scriptlet<T: Into<Scriptlet>>(mut self, ty: ScriptletType, content: T) -> Self {
+        self.scriptlets.insert(ty, content.into());
         self
     }
 
-    pub fn pre_uninstall_script<T: Into<String>>(mut self, content: T) -> Self {
-        self.pre_uninst_script = Some(content.into());
+    /// Run a script when packages matching the trigger conditions are installed or removed.
+    pub fn add_trigger(mut self, trigger: Trigger) -> Self {
+        self.triggers.push(trigger);
         self
     }
 
-    pub fn post_uninstall_script<T: Into<String>>(mut self, content: T) -> Self {
-        self.post_uninst_script = Some(content.into());
+    /// Run a script when files below the trigger prefixes are installed or removed.
+    pub fn add_file_trigger(mut self, trigger: FileTrigger) -> Self {
+        self.file_triggers.push(trigger);
         self
     }
 
@@ -266,8 +627,9 @@ impl RPMBuilder {
         self
     }
 
-    pub fn requires(mut self, dep: Dependency) -> Self {
-        self.requires.push(dep);
+    /// Accepts plain and [`RichDependency`] expressions.
+    pub fn requires<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.requires.push(dep.into());
         self
     }
 
@@ -276,8 +638,9 @@ impl RPMBuilder {
         self
     }
 
-    pub fn conflicts(mut self, dep: Dependency) -> Self {
-        self.conflicts.push(dep);
+    /// Accepts plain and [`RichDependency`] expressions.
+    pub fn conflicts<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.conflicts.push(dep.into());
         self
     }
 
@@ -286,11 +649,35 @@ impl RPMBuilder {
         self
     }
 
+    /// A weak dependency installed by default, but not required.
+    pub fn recommends<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.recommends.push(dep.into());
+        self
+    }
+
+    /// A weak dependency only shown to users as a suggestion.
+    pub fn suggests<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.suggests.push(dep.into());
+        self
+    }
+
+    /// A reverse [`recommends`](Self::recommends), this package is installed along with `dep`.
+    pub fn supplements<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.supplements.push(dep.into());
+        self
+    }
+
+    /// A reverse [`suggests`](Self::suggests).
+    pub fn enhances<D: Into<Dependency>>(mut self, dep: D) -> Self {
+        self.enhances.push(dep.into());
+        self
+    }
+
     /// build without a signature
     ///
     /// ignores a present key, if any
     pub fn build(self) -> Result<RPMPackage, RPMError> {
-        let (lead, header_idx_tag, content) = self.prepare_data()?;
+        let (lead, header_idx_tag, content, archive_size, file_signatures) = self.prepare_data()?;
 
         let mut header = Vec::with_capacity(128);
         header_idx_tag.write(&mut header)?;
@@ -301,12 +688,21 @@ impl RPMBuilder {
 
         let header_and_content_len = header.len() + content.len();
 
-        let digest_header = Header::<IndexSignatureTag>::builder()
+        let mut builder = Header::<IndexSignatureTag>::builder()
             .add_digest(
                 header_digest_sha1.as_str(),
                 header_and_content_digest_md5.as_slice(),
             )
-            .build(header_and_content_len as i32);
+            .payload_size(archive_size);
+        if !file_signatures.ima.is_empty() {
+            builder = builder.file_signatures(&file_signatures.ima);
+        }
+        if !file_signatures.verity.is_empty() {
+            builder = builder.verity_signatures(&file_signatures.verity);
+        }
+        let digest_header = builder
+            .reserved_space(SIGNATURE_RESERVED_SPACE)
+            .build(header_and_content_len as u64);
 
         let metadata = RPMPackageMetadata {
             lead,
@@ -319,51 +715,254 @@ impl RPMBuilder {
 
     /// use an external signer to sing and build
     ///
-    /// See `signature::Signing` for more details.
+    /// See `signature::Signing` for more details, the signature tags
+    /// follow [`Signing::algorithm`](signature::Signing::algorithm).
     #[cfg(feature = "signature-meta")]
     pub fn build_and_sign<S>(self, signer: S) -> Result<RPMPackage, RPMError>
     where
-        S: signature::Signing<crate::signature::algorithm::RSA>,
+        S: signature::Signing,
     {
-        let (lead, header_idx_tag, content) = self.prepare_data()?;
+        self.build_and_sign_with_mode(signer, signature::SignatureMode::default())
+    }
+
+    /// Like [`build_and_sign`](Self::build_and_sign), with the parts the signature spans
+    /// chosen by `mode`.
+    #[cfg(feature = "signature-meta")]
+    pub fn build_and_sign_with_mode<S>(
+        self,
+        signer: S,
+        mode: signature::SignatureMode,
+    ) -> Result<RPMPackage, RPMError>
+    where
+        S: signature::Signing,
+    {
+        let (lead, header_idx_tag, content, archive_size, file_signatures) = self.prepare_data()?;
 
         let mut header = Vec::with_capacity(128);
         header_idx_tag.write(&mut header)?;
         let header = header;
 
-        let (header_digest_sha1, header_and_content_digest_md5) =
-            Self::derive_hashes(header.as_slice(), content.as_slice())?;
-
         let header_and_content_len = header.len() + content.len();
 
-        let builder = Header::<IndexSignatureTag>::builder().add_digest(
-            header_digest_sha1.as_str(),
-            header_and_content_digest_md5.as_slice(),
-        );
-
-        let signature_header = {
-            let rsa_sig_header_only = signer.sign(header.as_slice())?;
-
-            let cursor = SeqCursor::new(&[header.as_slice(), content.as_slice()]);
-            let rsa_sig_header_and_archive = signer.sign(cursor)?;
+        let mut builder = Header::<IndexSignatureTag>::builder().payload_size(archive_size);
+        if !file_signatures.ima.is_empty() {
+            builder = builder.file_signatures(&file_signatures.ima);
+        }
+        if !file_signatures.verity.is_empty() {
+            builder = builder.verity_signatures(&file_signatures.verity);
+        }
 
-            builder
-                .add_signature(
-                    rsa_sig_header_only.as_ref(),
-                    rsa_sig_header_and_archive.as_ref(),
-                )
-                .build(header_and_content_len as i32)
+        let sig_header_only = signer.sign(header.as_slice())?;
+
+        let signature_header = match mode {
+            signature::SignatureMode::HeaderAndPayload => {
+                let (header_digest_sha1, header_and_content_digest_md5) =
+                    Self::derive_hashes(header.as_slice(), content.as_slice())?;
+
+                let cursor = SeqCursor::new(&[header.as_slice(), content.as_slice()]);
+                let sig_header_and_archive = signer.sign(cursor)?;
+
+                builder
+                    .add_digest(
+                        header_digest_sha1.as_str(),
+                        header_and_content_digest_md5.as_slice(),
+                    )
+                    .add_signature_with_algorithm(
+                        signer.algorithm(),
+                        sig_header_only.as_ref(),
+                        sig_header_and_archive.as_ref(),
+                    )
+                    .build(header_and_content_len as u64)
+            }
+            signature::SignatureMode::HeaderOnly => {
+                let header_digest_sha256 = FileDigestAlgorithm::Sha2_256.digest(&header)?;
+                builder
+                    .add_header_digest(hex::encode(header_digest_sha256).as_str())
+                    .add_header_signature(signer.algorithm(), sig_header_only.as_ref())
+                    .build(header_and_content_len as u64)
+            }
         };
 
         let metadata = RPMPackageMetadata {
             lead,
-            signature: signature_header,
+            signature: signature_header.with_signature_reserved_space(),
             header: header_idx_tag,
         };
         let pkg = RPMPackage { metadata, content };
         Ok(pkg)
     }
 
+    /// Files of 4 GiB and more need 64 bit sizes and the stripped cpio format.
+    fn has_large_files(&self) -> bool {
+        self.files
+            .values()
+            .any(|entry| entry.size > self.large_file_threshold)
+    }
+
+    /// The `rpmlib(...)` features an installing rpm needs to support for this package.
+    ///
+    /// Makes older rpm versions refuse the package upfront instead of failing halfway.
+    fn rpmlib_requires(&self) -> Vec<Dependency> {
+        let mut requires = Vec::new();
+        if !self.files.is_empty() {
+            requires.push(Dependency::rpm_lib("CompressedFileNames", "3.0.4-1"));
+            if self.file_digest_algorithm != FileDigestAlgorithm::Md5 {
+                requires.push(Dependency::rpm_lib("FileDigests", "4.6.0-1"));
+            }
+            if !self.source_package {
+                requires.push(Dependency::rpm_lib("PayloadFilesHavePrefix", "4.0-1"));
+            }
+        }
+        if let Compressor::Zstd(_) = self.compressor {
+            requires.push(Dependency::rpm_lib("PayloadIsZstd", "5.4.18-1"));
+        }
+        if self.files.values().any(|entry| entry.caps.is_some()) {
+            requires.push(Dependency::rpm_lib("FileCaps", "4.6.1-1"));
+        }
+        if self.has_large_files() {
+            requires.push(Dependency::rpm_lib("LargeFiles", "4.12.0-1"));
+        }
+        let rich_dependencies = self
+            .requires
+            .iter()
+            .chain(self.provides.iter())
+            .chain(self.conflicts.iter())
+            .chain(self.obsoletes.iter())
+            .chain(self.recommends.iter())
+            .chain(self.suggests.iter())
+            .chain(self.supplements.iter())
+            .chain(self.enhances.iter())
+            .any(|dep| dep.dep_name.starts_with('('));
+        if rich_dependencies {
+            requires.push(Dependency::rpm_lib("RichDependencies", "4.12.0-1"));
+        }
+
+        let scriptlets = self
+            .scriptlets
+            .values()
+            .chain(self.triggers.iter().map(|trigger| &trigger.scriptlet))
+            .chain(self.file_triggers.iter().map(|trigger| &trigger.scriptlet));
+        let (mut interpreter_args, mut lua, mut expansion) = (false, false, false);
+        for scriptlet in scriptlets {
+            interpreter_args |= scriptlet.program.len() > 1;
+            lua |= scriptlet.is_lua();
+            expansion |= scriptlet.flags & RPMSCRIPT_FLAG_EXPAND != 0;
+        }
+        if interpreter_args {
+            requires.push(Dependency::rpm_lib("ScriptletInterpreterArgs", "4.0.3-1"));
+        }
+        if lua {
+            requires.push(Dependency::rpm_lib("BuiltinLuaScripts", "4.2.2-1"));
+        }
+        if expansion {
+            requires.push(Dependency::rpm_lib("ScriptletExpansion", "4.9.0-1"));
+        }
+        if !self.file_triggers.is_empty() {
+            requires.push(Dependency::rpm_lib("FileTriggers", "4.12.90-1"));
+        }
+        requires
+    }
+
+    /// Encode triggers into the index arrays of `tags`.
+    ///
+    /// Adds the requirements on the trigger interpreters to `requires`.
+    fn encode_triggers<I>(
+        tags: &TriggerIndexTags,
+        triggers: I,
+        requires: &mut Vec<Dependency>,
+    ) -> Result<Vec<IndexEntry<IndexTag>>, RPMError>
+    where
+        I: IntoIterator<Item = (Trigger, Option<u32>)>,
+    {
+        let mut scripts = Vec::new();
+        let mut progs = Vec::new();
+        let mut script_flags = Vec::new();
+        let mut priorities = Vec::new();
+        let mut names = Vec::new();
+        let mut versions = Vec::new();
+        let mut flags = Vec::new();
+        let mut indexes = Vec::new();
+
+        for (index, (trigger, priority)) in triggers.into_iter().enumerate() {
+            if trigger.conditions.is_empty() {
+                return Err(RPMError::InvalidTrigger("no conditions given"));
+            }
+            let interpreter = match trigger.scriptlet.program.as_slice() {
+                [] => DEFAULT_SCRIPTLET_INTERPRETER.to_string(),
+                [interpreter] => interpreter.to_owned(),
+                _ => {
+                    return Err(RPMError::InvalidTrigger(
+                        "interpreter arguments are not allowed",
+                    ))
+                }
+            };
+            if !trigger.scriptlet.is_lua() {
+                requires.push(Dependency::new(
+                    interpreter.clone(),
+                    RPMSENSE_INTERP | trigger.ty.sense(),
+                    "".to_string(),
+                ));
+            }
+            for condition in trigger.conditions {
+                names.push(condition.dep_name);
+                versions.push(condition.version);
+                flags.push((condition.sense | trigger.ty.sense()) as i32);
+                indexes.push(index as i32);
+            }
+            scripts.push(trigger.scriptlet.script);
+            progs.push(interpreter);
+            script_flags.push(trigger.scriptlet.flags as i32);
+            priorities.push(priority.unwrap_or(DEFAULT_FILE_TRIGGER_PRIORITY) as i32);
+        }
+
+        let offset = 0;
+        let mut records = Vec::new();
+        if scripts.is_empty() {
+            return Ok(records);
+        }
+        records.push(IndexEntry::new(
+            tags.scripts,
+            offset,
+            IndexData::StringArray(scripts),
+        ));
+        records.push(IndexEntry::new(
+            tags.progs,
+            offset,
+            IndexData::StringArray(progs),
+        ));
+        if script_flags.iter().any(|flags| *flags != 0) {
+            records.push(IndexEntry::new(
+                tags.script_flags,
+                offset,
+                IndexData::Int32(script_flags),
+            ));
+        }
+        records.push(IndexEntry::new(
+            tags.names,
+            offset,
+            IndexData::StringArray(names),
+        ));
+        records.push(IndexEntry::new(
+            tags.versions,
+            offset,
+            IndexData::StringArray(versions),
+        ));
+        records.push(IndexEntry::new(tags.flags, offset, IndexData::Int32(flags)));
+        records.push(IndexEntry::new(
+            tags.indexes,
+            offset,
+            IndexData::Int32(indexes),
+        ));
+        if let Some(priorities_tag) = tags.priorities {
+            records.push(IndexEntry::new(
+                priorities_tag,
+                offset,
+                IndexData::Int32(priorities),
+            ));
+        }
+        Ok(records)
+    }
+
     /// use prepared data but make sure the signatures are
     fn derive_hashes(header: &[u8], content: &[u8]) -> Result<(String, Vec<u8>), RPMError> {
         let digest_md5 = {
@@ -371,8 +970,8 @@ impl RPMBuilder {
 
             // accross header index and content (compressed or uncompressed, depends on configuration)
             let mut hasher = md5::Md5::default();
-            hasher.update(&header);
-            hasher.update(&content);
+            hasher.update(header);
+            hasher.update(content);
             let digest_md5 = hasher.finalize();
             digest_md5.to_vec()
         };
@@ -382,7 +981,7 @@ impl RPMBuilder {
             use sha1::Digest;
 
             let mut hasher = sha1::Sha1::default();
-            hasher.update(&header);
+            hasher.update(header);
             let digest_sha1 = hasher.finalize();
             hex::encode(digest_sha1)
         };
@@ -393,14 +992,86 @@ impl RPMBuilder {
     /// prepapre all rpm headers including content
     ///
     /// @todo split this into multiple `fn`s, one per `IndexTag`-group.
-    fn prepare_data(mut self) -> Result<(Lead, Header<IndexTag>, Vec<u8>), RPMError> {
+    #[allow(clippy::type_complexity)]
+    fn prepare_data(
+        mut self,
+    ) -> Result<(Lead, Header<IndexTag>, Vec<u8>, u64, FileSignatures), RPMError> {
         // signature depends on header and payload. So we build these two first.
         // then the signature. Then we stitch all toghether.
         // Lead is not important. just build it here
 
-        let lead = Lead::new(&self.name);
+        let lead = Lead::new(&self.name, self.source_package);
+
+        if !self.source_package {
+            if !self.sources.is_empty() {
+                return Err(RPMError::SourcePackageOnly("sources"));
+            }
+            if !self.patches.is_empty() {
+                return Err(RPMError::SourcePackageOnly("patches"));
+            }
+            if !self.build_requires.is_empty() {
+                return Err(RPMError::SourcePackageOnly("build requirements"));
+            }
+            if self.files.values().any(|entry| entry.dir.is_empty()) {
+                return Err(RPMError::SourcePackageOnly("spec files"));
+            }
+        } else {
+            // source packages only carry the flat files added by with_spec_file & co
+            if self.files.values().any(|entry| !entry.dir.is_empty()) {
+                return Err(RPMError::BinaryPackageOnly("files with a destination path"));
+            }
+            let mut names = BTreeSet::new();
+            if let Some(name) = self
+                .sources
+                .iter()
+                .chain(self.patches.iter())
+                .find(|name| !names.insert(name.as_str()))
+            {
+                return Err(RPMError::DuplicateSourceFile(name.clone()));
+            }
+        }
+        // like rpmbuild, drop trailing slashes of the prefixes
+        let prefixes = self
+            .prefixes
+            .iter()
+            .map(|prefix| match prefix.trim_end_matches('/') {
+                "" => "/".to_string(),
+                prefix => prefix.to_string(),
+            })
+            .collect::<Vec<_>>();
+        if let Some(prefix) = prefixes.iter().find(|prefix| !prefix.starts_with('/')) {
+            return Err(RPMError::InvalidDestinationPath {
+                path: prefix.to_owned(),
+                desc: "relocatable prefixes must be absolute",
+            });
+        }
+        if !prefixes.is_empty() {
+            for entry in self.files.values() {
+                let path = format!("{}{}", entry.dir, entry.base_name);
+                let relocatable = prefixes.iter().any(|prefix| {
+                    prefix == "/"
+                        || path == *prefix
+                        || path
+                            .strip_prefix(prefix.as_str())
+                            .map_or(false, |rest| rest.starts_with('/'))
+                });
+                if !relocatable {
+                    return Err(RPMError::InvalidDestinationPath {
+                        path,
+                        desc: "not below any relocatable prefix",
+                    });
+                }
+            }
+        }
 
-        let mut ino_index = 1;
+        // like rpmbuild, binary packages reference the source package they are built from
+        let source_rpm = if self.source_package {
+            None
+        } else {
+            Some(self.source_rpm.take().unwrap_or_else(|| {
+                format!("{}-{}-{}.src.rpm", self.name, self.version, self.release)
+            }))
+        };
 
         let mut file_sizes = Vec::new();
         let mut file_modes = Vec::new();
@@ -417,10 +1088,20 @@ impl RPMBuilder {
         let mut file_verify_flags = Vec::new();
         let mut dir_indixes = Vec::new();
         let mut base_names = Vec::new();
+        let mut file_contexts = Vec::new();
+        let mut file_xattrs = Vec::new();
+        let mut file_caps = Vec::new();
+        let mut file_colors = Vec::new();
+        let mut file_classes = Vec::new();
 
         let mut combined_file_sizes = 0;
+        let large_files = self.has_large_files();
+        // the uncompressed size of the cpio archive
+        let mut archive_size = 0;
 
-        for (cpio_path, entry) in self.files.iter() {
+        for (idx, (cpio_path, entry)) in self.files.iter().enumerate() {
+            // inodes are 1-based
+            let ino_index = idx + 1;
             combined_file_sizes += entry.size;
             file_sizes.push(entry.size);
             file_modes.push(entry.mode.into());
@@ -428,8 +1109,14 @@ impl RPMBuilder {
             // Who knows, who cares.
             file_rdevs.push(0);
             file_devices.push(1);
-            file_mtimes.push(entry.modified_at);
-            file_hashes.push(entry.sha_checksum.to_owned());
+            file_mtimes.push(match self.source_date {
+                Some(source_date) => entry.modified_at.min(source_date),
+                None => entry.modified_at,
+            });
+            file_hashes.push(hex::encode(
+                self.file_digest_algorithm
+                    .digest(entry.content.as_ref().unwrap())?,
+            ));
             file_linktos.push(entry.link.to_owned());
             file_flags.push(entry.flag);
             file_usernames.push(entry.user.to_owned());
@@ -444,21 +1131,96 @@ impl RPMBuilder {
             dir_indixes.push(index as i32);
             base_names.push(entry.base_name.to_owned());
             file_verify_flags.push(-1);
+            file_contexts.push(entry.selinux_context.clone().unwrap_or_default());
+            file_xattrs.push(encode_xattrs(&entry.xattrs));
+            file_caps.push(entry.caps.clone().unwrap_or_default());
             let content = entry.content.to_owned().unwrap();
-            let mut writer = cpio::newc::Builder::new(cpio_path)
-                .mode(entry.mode.into())
-                .ino(ino_index as u32)
-                .uid(self.uid.unwrap_or(0))
-                .gid(self.gid.unwrap_or(0))
-                .write(&mut self.compressor, content.len() as u32);
-
-            writer.write_all(&content)?;
-            writer.finish()?;
+            let class = super::file_class::classify(&content, entry.mode);
+            file_colors.push(class.color as i32);
+            file_classes.push(class.class);
+            let content_padding = cpio_padding(content.len() as u64);
+            if large_files {
+                // rpm's stripped cpio format only refers to the file index,
+                // all other metadata is taken from the header
+                let magic = format!("{}{:08x}", CPIO_STRIPPED_MAGIC, idx);
+                self.compressor.write_all(magic.as_bytes())?;
+                self.compressor
+                    .write_all(&[0; 4][..cpio_padding(magic.len() as u64)])?;
+                self.compressor.write_all(&content)?;
+                self.compressor.write_all(&[0; 4][..content_padding])?;
+                archive_size += (magic.len() + cpio_padding(magic.len() as u64)) as u64;
+            } else {
+                let mut writer = cpio::newc::Builder::new(cpio_path)
+                    .mode(entry.mode.into())
+                    .ino(ino_index as u32)
+                    .uid(self.uid.unwrap_or(0))
+                    .gid(self.gid.unwrap_or(0))
+                    .write(&mut self.compressor, content.len() as u32);
+
+                writer.write_all(&content)?;
+                writer.finish()?;
+                let name_len = CPIO_NEWC_HEADER_LEN + cpio_path.len() as u64 + 1;
+                archive_size += name_len + cpio_padding(name_len) as u64;
+            }
+            archive_size += content.len() as u64 + content_padding as u64;
+        }
 
-            ino_index += 1;
+        if self.source_package {
+            // the build requirements of a source package are its requirements
+            let build_requires = std::mem::take(&mut self.build_requires);
+            self.requires.extend(build_requires);
+        } else {
+            self.requires.push(Dependency::any("/bin/sh".to_string()));
+        }
+        let rpmlib_requires = self.rpmlib_requires();
+        self.requires.extend(rpmlib_requires);
+
+        // the interpreters must be present before the scriptlets run
+        for (ty, scriptlet) in self.scriptlets.iter() {
+            if let Some(interpreter) = scriptlet.program.first() {
+                if !scriptlet.is_lua() {
+                    self.requires.push(Dependency::new(
+                        interpreter.to_owned(),
+                        ty.interpreter_sense(),
+                        "".to_string(),
+                    ));
+                }
+            }
         }
 
-        self.requires.push(Dependency::any("/bin/sh".to_string()));
+        let mut trigger_records = Self::encode_triggers(
+            &TriggerIndexTags::PACKAGE,
+            self.triggers.into_iter().map(|trigger| (trigger, None)),
+            &mut self.requires,
+        )?;
+        for scope in [FileTriggerScope::Package, FileTriggerScope::Transaction] {
+            let mut file_triggers = Vec::new();
+            for trigger in self.file_triggers.iter().filter(|t| t.scope == scope) {
+                if trigger.ty == TriggerType::PreInstall {
+                    return Err(RPMError::InvalidTrigger(
+                        "file triggers can not run before installation",
+                    ));
+                }
+                let conditions = trigger
+                    .prefixes
+                    .iter()
+                    .map(|prefix| Dependency::any(prefix.as_str()))
+                    .collect();
+                file_triggers.push((
+                    Trigger {
+                        ty: trigger.ty,
+                        conditions,
+                        scriptlet: trigger.scriptlet.clone(),
+                    },
+                    Some(trigger.priority),
+                ));
+            }
+            trigger_records.extend(Self::encode_triggers(
+                TriggerIndexTags::for_scope(scope),
+                file_triggers,
+                &mut self.requires,
+            )?);
+        }
 
         self.provides
             .push(Dependency::eq(self.name.clone(), self.version.clone()));
@@ -467,6 +1229,39 @@ impl RPMBuilder {
             self.version.clone(),
         ));
 
+        // per file indexes into the provides and requires, like rpmbuild
+        #[allow(unused_mut)]
+        let mut file_dependencies: Option<(Vec<i32>, Vec<i32>, Vec<i32>)> = None;
+        #[cfg(feature = "dependency-generator")]
+        if self.generate_dependencies && !self.files.is_empty() {
+            let mut file_depends_x = Vec::new();
+            let mut file_depends_n = Vec::new();
+            let mut depends_dict = Vec::new();
+            for entry in self.files.values() {
+                let content = entry.content.as_deref().unwrap_or_default();
+                let deps = super::dependency_generator::file_dependencies(content, entry.mode);
+                file_depends_x.push(depends_dict.len() as i32);
+                file_depends_n.push((deps.provides.len() + deps.requires.len()) as i32);
+                let kinds = [
+                    (b'P', deps.provides, &mut self.provides),
+                    (b'R', deps.requires, &mut self.requires),
+                ];
+                for (kind, generated, existing) in kinds {
+                    for dep in generated {
+                        let index = match existing.iter().position(|d| d == &dep) {
+                            Some(index) => index,
+                            None => {
+                                existing.push(dep);
+                                existing.len() - 1
+                            }
+                        };
+                        depends_dict.push(((kind as u32) << 24 | index as u32) as i32);
+                    }
+                }
+            }
+            file_dependencies = Some((file_depends_x, file_depends_n, depends_dict));
+        }
+
         let mut provide_names = Vec::new();
         let mut provide_flags = Vec::new();
         let mut provide_versions = Vec::new();
@@ -509,6 +1304,42 @@ impl RPMBuilder {
 
         let offset = 0;
 
+        let size_entry = if combined_file_sizes > self.large_file_threshold {
+            IndexEntry::new(
+                IndexTag::RPMTAG_LONGSIZE,
+                offset,
+                IndexData::Int64(vec![combined_file_sizes as i64]),
+            )
+        } else {
+            IndexEntry::new(
+                IndexTag::RPMTAG_SIZE,
+                offset,
+                IndexData::Int32(vec![combined_file_sizes as u32 as i32]),
+            )
+        };
+        let file_sizes_entry = if large_files {
+            IndexEntry::new(
+                IndexTag::RPMTAG_LONGFILESIZES,
+                offset,
+                IndexData::Int64(file_sizes.into_iter().map(|size| size as i64).collect()),
+            )
+        } else {
+            IndexEntry::new(
+                IndexTag::RPMTAG_FILESIZES,
+                offset,
+                IndexData::Int32(
+                    file_sizes
+                        .into_iter()
+                        .map(|size| size as u32 as i32)
+                        .collect(),
+                ),
+            )
+        };
+
+        let desc = self.desc;
+        let summary = self.summary.unwrap_or_else(|| desc.clone());
+        let group = self.group.unwrap_or_else(|| "Unspecified".to_string());
+
         let mut actual_records = if self.files.is_empty() {
             // if we have an empty RPM, we have to leave out all file related index entries.
             vec![
@@ -540,34 +1371,29 @@ impl RPMBuilder {
                 IndexEntry::new(
                     IndexTag::RPMTAG_DESCRIPTION,
                     offset,
-                    IndexData::StringTag(self.desc.clone()),
+                    IndexData::StringTag(desc),
                 ),
                 IndexEntry::new(
                     IndexTag::RPMTAG_SUMMARY,
                     offset,
-                    IndexData::StringTag(self.desc),
-                ),
-                IndexEntry::new(
-                    IndexTag::RPMTAG_SIZE,
-                    offset,
-                    IndexData::Int32(vec![combined_file_sizes]),
+                    IndexData::StringTag(summary),
                 ),
+                size_entry,
                 IndexEntry::new(
                     IndexTag::RPMTAG_LICENSE,
                     offset,
                     IndexData::StringTag(self.license),
                 ),
-                // https://fedoraproject.org/wiki/RPMGroups
-                // IndexEntry::new(IndexTag::RPMTAG_GROUP, offset, IndexData::I18NString(group)),
                 IndexEntry::new(
                     IndexTag::RPMTAG_OS,
                     offset,
                     IndexData::StringTag("linux".to_string()),
                 ),
+                // https://fedoraproject.org/wiki/RPMGroups
                 IndexEntry::new(
                     IndexTag::RPMTAG_GROUP,
                     offset,
-                    IndexData::I18NString(vec!["Unspecified".to_string()]),
+                    IndexData::I18NString(vec![group]),
                 ),
                 IndexEntry::new(
                     IndexTag::RPMTAG_ARCH,
@@ -620,34 +1446,29 @@ impl RPMBuilder {
                 IndexEntry::new(
                     IndexTag::RPMTAG_DESCRIPTION,
                     offset,
-                    IndexData::StringTag(self.desc.clone()),
+                    IndexData::StringTag(desc),
                 ),
                 IndexEntry::new(
                     IndexTag::RPMTAG_SUMMARY,
                     offset,
-                    IndexData::StringTag(self.desc),
-                ),
-                IndexEntry::new(
-                    IndexTag::RPMTAG_SIZE,
-                    offset,
-                    IndexData::Int32(vec![combined_file_sizes]),
+                    IndexData::StringTag(summary),
                 ),
+                size_entry,
                 IndexEntry::new(
                     IndexTag::RPMTAG_LICENSE,
                     offset,
                     IndexData::StringTag(self.license),
                 ),
-                // https://fedoraproject.org/wiki/RPMGroups
-                // IndexEntry::new(IndexTag::RPMTAG_GROUP, offset, IndexData::I18NString(group)),
                 IndexEntry::new(
                     IndexTag::RPMTAG_OS,
                     offset,
                     IndexData::StringTag("linux".to_string()),
                 ),
+                // https://fedoraproject.org/wiki/RPMGroups
                 IndexEntry::new(
                     IndexTag::RPMTAG_GROUP,
                     offset,
-                    IndexData::I18NString(vec!["Unspecified".to_string()]),
+                    IndexData::I18NString(vec![group]),
                 ),
                 IndexEntry::new(
                     IndexTag::RPMTAG_ARCH,
@@ -659,11 +1480,7 @@ impl RPMBuilder {
                     offset,
                     IndexData::StringTag("cpio".to_string()),
                 ),
-                IndexEntry::new(
-                    IndexTag::RPMTAG_FILESIZES,
-                    offset,
-                    IndexData::Int32(file_sizes),
-                ),
+                file_sizes_entry,
                 IndexEntry::new(
                     IndexTag::RPMTAG_FILEMODES,
                     offset,
@@ -727,7 +1544,7 @@ impl RPMBuilder {
                 IndexEntry::new(
                     IndexTag::RPMTAG_FILEDIGESTALGO,
                     offset,
-                    IndexData::Int32(vec![8]),
+                    IndexData::Int32(vec![self.file_digest_algorithm as i32]),
                 ),
                 IndexEntry::new(
                     IndexTag::RPMTAG_FILEVERIFYFLAGS,
@@ -777,6 +1594,160 @@ impl RPMBuilder {
             ));
         }
 
+        if !file_classes.is_empty() {
+            let class_dict = file_classes.iter().cloned().collect::<BTreeSet<_>>();
+            let class_dict = class_dict.into_iter().collect::<Vec<_>>();
+            let class_indexes = file_classes
+                .iter()
+                .map(|class| class_dict.binary_search(class).unwrap() as i32)
+                .collect();
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILECOLORS,
+                offset,
+                IndexData::Int32(file_colors),
+            ));
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILECLASS,
+                offset,
+                IndexData::Int32(class_indexes),
+            ));
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_CLASSDICT,
+                offset,
+                IndexData::StringArray(class_dict),
+            ));
+        }
+
+        if file_contexts.iter().any(|context| !context.is_empty()) {
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILECONTEXTS,
+                offset,
+                IndexData::StringArray(file_contexts),
+            ));
+        }
+
+        if file_caps.iter().any(|caps| !caps.is_empty()) {
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILECAPS,
+                offset,
+                IndexData::StringArray(file_caps),
+            ));
+        }
+
+        if file_xattrs.iter().any(|xattrs| !xattrs.is_empty()) {
+            // the empty set goes first, so files without any xattrs refer to index 0
+            let mut xattrs_dict = vec![String::new()];
+            xattrs_dict.extend(
+                file_xattrs
+                    .iter()
+                    .filter(|xattrs| !xattrs.is_empty())
+                    .cloned()
+                    .collect::<BTreeSet<_>>(),
+            );
+            let xattrs_indexes = file_xattrs
+                .iter()
+                .map(|xattrs| {
+                    xattrs_dict
+                        .iter()
+                        .position(|entry| entry == xattrs)
+                        .unwrap() as i32
+                })
+                .collect();
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_XATTRSDICT,
+                offset,
+                IndexData::StringArray(xattrs_dict),
+            ));
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILEXATTRSX,
+                offset,
+                IndexData::Int32(xattrs_indexes),
+            ));
+        }
+
+        let optional_tags = [
+            (IndexTag::RPMTAG_URL, self.url),
+            (IndexTag::RPMTAG_VENDOR, self.vendor),
+            (IndexTag::RPMTAG_PACKAGER, self.packager),
+            (IndexTag::RPMTAG_DISTRIBUTION, self.distribution),
+            (IndexTag::RPMTAG_BUGURL, self.bug_url),
+            (IndexTag::RPMTAG_VCS, self.vcs),
+            (IndexTag::RPMTAG_BUILDHOST, self.build_host),
+            (IndexTag::RPMTAG_SOURCERPM, source_rpm),
+            (IndexTag::RPMTAG_DISTTAG, self.dist_tag),
+            (IndexTag::RPMTAG_MODULARITYLABEL, self.modularity_label),
+        ];
+        for (tag, value) in optional_tags {
+            if let Some(value) = value {
+                actual_records.push(IndexEntry::new(tag, offset, IndexData::StringTag(value)));
+            }
+        }
+        if let Some((file_depends_x, file_depends_n, depends_dict)) = file_dependencies {
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILEDEPENDSX,
+                offset,
+                IndexData::Int32(file_depends_x),
+            ));
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_FILEDEPENDSN,
+                offset,
+                IndexData::Int32(file_depends_n),
+            ));
+            if !depends_dict.is_empty() {
+                actual_records.push(IndexEntry::new(
+                    IndexTag::RPMTAG_DEPENDSDICT,
+                    offset,
+                    IndexData::Int32(depends_dict),
+                ));
+            }
+        }
+
+        if !prefixes.is_empty() {
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_PREFIXES,
+                offset,
+                IndexData::StringArray(prefixes),
+            ));
+        }
+
+        if self.source_package {
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_SOURCEPACKAGE,
+                offset,
+                IndexData::Int32(vec![1]),
+            ));
+            let source_lists = [
+                (IndexTag::RPMTAG_SOURCE, self.sources),
+                (IndexTag::RPMTAG_PATCH, self.patches),
+            ];
+            for (tag, names) in source_lists {
+                if !names.is_empty() {
+                    actual_records.push(IndexEntry::new(
+                        tag,
+                        offset,
+                        IndexData::StringArray(names),
+                    ));
+                }
+            }
+            let excluded_lists = [
+                (IndexTag::RPMTAG_NOSOURCE, self.no_sources),
+                (IndexTag::RPMTAG_NOPATCH, self.no_patches),
+            ];
+            for (tag, numbers) in excluded_lists {
+                if !numbers.is_empty() {
+                    actual_records.push(IndexEntry::new(tag, offset, IndexData::Int32(numbers)));
+                }
+            }
+        }
+
+        if let Some(build_time) = self.build_time.or(self.source_date) {
+            actual_records.push(IndexEntry::new(
+                IndexTag::RPMTAG_BUILDTIME,
+                offset,
+                IndexData::Int32(vec![build_time]),
+            ));
+        }
+
         if !self.changelog_authors.is_empty() {
             actual_records.push(IndexEntry::new(
                 IndexTag::RPMTAG_CHANGELOGNAME,
@@ -849,69 +1820,130 @@ impl RPMBuilder {
             ));
         }
 
-        if self.pre_inst_script.is_some() {
-            actual_records.push(IndexEntry::new(
-                IndexTag::RPMTAG_PREIN,
-                offset,
-                IndexData::StringTag(self.pre_inst_script.unwrap()),
-            ));
-        }
-        if self.post_inst_script.is_some() {
+        let weak_dependencies = [
+            (
+                self.recommends,
+                IndexTag::RPMTAG_RECOMMENDNAME,
+                IndexTag::RPMTAG_RECOMMENDVERSION,
+                IndexTag::RPMTAG_RECOMMENDFLAGS,
+            ),
+            (
+                self.suggests,
+                IndexTag::RPMTAG_SUGGESTNAME,
+                IndexTag::RPMTAG_SUGGESTVERSION,
+                IndexTag::RPMTAG_SUGGESTFLAGS,
+            ),
+            (
+                self.supplements,
+                IndexTag::RPMTAG_SUPPLEMENTNAME,
+                IndexTag::RPMTAG_SUPPLEMENTVERSION,
+                IndexTag::RPMTAG_SUPPLEMENTFLAGS,
+            ),
+            (
+                self.enhances,
+                IndexTag::RPMTAG_ENHANCENAME,
+                IndexTag::RPMTAG_ENHANCEVERSION,
+                IndexTag::RPMTAG_ENHANCEFLAGS,
+            ),
+        ];
+        for (deps, name_tag, version_tag, flags_tag) in weak_dependencies {
+            if deps.is_empty() {
+                continue;
+            }
+            let mut names = Vec::new();
+            let mut flags = Vec::new();
+            let mut versions = Vec::new();
+            for d in deps.into_iter() {
+                names.push(d.dep_name);
+                flags.push(d.sense as i32);
+                versions.push(d.version);
+            }
             actual_records.push(IndexEntry::new(
-                IndexTag::RPMTAG_POSTIN,
+                name_tag,
                 offset,
-                IndexData::StringTag(self.post_inst_script.unwrap()),
+                IndexData::StringArray(names),
             ));
-        }
-
-        if self.pre_uninst_script.is_some() {
             actual_records.push(IndexEntry::new(
-                IndexTag::RPMTAG_PREUN,
+                version_tag,
                 offset,
-                IndexData::StringTag(self.pre_uninst_script.unwrap()),
+                IndexData::StringArray(versions),
             ));
+            actual_records.push(IndexEntry::new(flags_tag, offset, IndexData::Int32(flags)));
         }
 
-        if self.post_uninst_script.is_some() {
+        for (ty, scriptlet) in self.scriptlets {
+            let tags = ty.index_tags();
             actual_records.push(IndexEntry::new(
-                IndexTag::RPMTAG_POSTUN,
+                tags.script,
                 offset,
-                IndexData::StringTag(self.post_uninst_script.unwrap()),
+                IndexData::StringTag(scriptlet.script),
             ));
+            // like rpmbuild, only use an array if the interpreter takes arguments
+            let mut program = scriptlet.program;
+            match program.len() {
+                0 => {}
+                1 => actual_records.push(IndexEntry::new(
+                    tags.program,
+                    offset,
+                    IndexData::StringTag(program.remove(0)),
+                )),
+                _ => actual_records.push(IndexEntry::new(
+                    tags.program,
+                    offset,
+                    IndexData::StringArray(program),
+                )),
+            }
+            if scriptlet.flags != 0 {
+                actual_records.push(IndexEntry::new(
+                    tags.flags,
+                    offset,
+                    IndexData::Int32(vec![scriptlet.flags as i32]),
+                ));
+            }
         }
 
-        let header = Header::from_entries(actual_records, IndexTag::RPMTAG_HEADERIMMUTABLE);
-
-        //those parts seem to break on fedora installations, but it does not seem to matter for centos.
-        // if it turns out that those parts are not really required, we will delete the following comments
-
-        // self.requires.push(Dependency::rpm_lib(
-        //     "rpmlib(VersionedDependencies)".to_string(),
-        //     "3.0.3-1".to_string(),
-        // ));
-
-        // self.requires.push(Dependency::rpm_lib(
-        //     "rpmlib(PayloadFilesHavePrefix)".to_string(),
-        //     "4.0-1".to_string(),
-        // ));
-
-        // self.requires.push(Dependency::rpm_lib(
-        //     "rpmlib(CompressedFileNames)".to_string(),
-        //     "3.0.4-1".to_string(),
-        // ));
-
-        // self.requires.push(Dependency::rpm_lib(
-        //     "rpmlib(PayloadIsXz)".to_string(),
-        //     "5.2-1".to_string(),
-        // ));
-        // self.requires.push(Dependency::rpm_lib(
-        //     "rpmlib(FileDigests)".to_string(),
-        //     "4.6.0-1".to_string(),
-        // ));
+        actual_records.extend(trigger_records);
 
         self.compressor = cpio::newc::trailer(self.compressor)?;
+        let trailer_len = CPIO_NEWC_HEADER_LEN + CPIO_TRAILER_NAME.len() as u64 + 1;
+        archive_size += trailer_len + cpio_padding(trailer_len) as u64;
         let content = self.compressor.finish_compression()?;
 
-        Ok((lead, header, content))
+        // the digest of the compressed payload, which header-only signatures rely on
+        let payload_digest = FileDigestAlgorithm::Sha2_256.digest(&content)?;
+        actual_records.push(IndexEntry::new(
+            IndexTag::RPMTAG_PAYLOADDIGEST,
+            offset,
+            IndexData::StringArray(vec![hex::encode(payload_digest)]),
+        ));
+        actual_records.push(IndexEntry::new(
+            IndexTag::RPMTAG_PAYLOADDIGESTALGO,
+            offset,
+            IndexData::Int32(vec![FileDigestAlgorithm::Sha2_256 as i32]),
+        ));
+
+        let header = Header::from_entries(actual_records, IndexTag::RPMTAG_HEADERIMMUTABLE);
+
+        #[allow(unused_mut)]
+        let mut file_signatures = FileSignatures::default();
+        // IMA signatures are made over the file digests, so the header must be complete
+        #[cfg(feature = "signature-ima")]
+        if let Some(signer) = &self.file_signer {
+            file_signatures.ima = signer.sign_file_digests(&header)?;
+        }
+        // fs-verity signatures in the order of the files, only regular files are signed
+        #[cfg(feature = "signature-verity")]
+        if let Some(signer) = &self.verity_signer {
+            file_signatures.verity = self
+                .files
+                .values()
+                .map(|entry| match (entry.mode, &entry.content) {
+                    (FileMode::Regular { .. }, Some(content)) => signer.sign_content(content),
+                    _ => Ok(String::new()),
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok((lead, header, content, archive_size, file_signatures))
     }
 }
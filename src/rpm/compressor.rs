@@ -29,7 +29,15 @@ impl std::str::FromStr for Compressor {
     fn from_str(raw: &str) -> Result<Self, Self::Err> {
         match raw {
             "none" => Ok(Compressor::None(Vec::new())),
-            "gzip" => Ok(Compressor::Gzip(libflate::gzip::Encoder::new(Vec::new())?)),
+            // a fixed modification time keeps the payload reproducible
+            "gzip" => Ok(Compressor::Gzip(libflate::gzip::Encoder::with_options(
+                Vec::new(),
+                libflate::gzip::EncodeOptions::new().header(
+                    libflate::gzip::HeaderBuilder::new()
+                        .modification_time(0)
+                        .finish(),
+                ),
+            )?)),
             "zstd" => Ok(Compressor::Zstd(zstd::stream::Encoder::new(
                 Vec::new(),
                 19,
@@ -63,6 +71,20 @@ impl Compressor {
     }
 }
 
+/// A reader of the uncompressed payload, compressed with `compressor` as named in the header.
+#[cfg(feature = "signature-verity")]
+pub(crate) fn decompress<'a>(
+    compressor: &str,
+    content: &'a [u8],
+) -> Result<Box<dyn std::io::Read + 'a>, RPMError> {
+    match compressor {
+        "none" => Ok(Box::new(content)),
+        "gzip" => Ok(Box::new(libflate::gzip::Decoder::new(content)?)),
+        "zstd" => Ok(Box::new(zstd::stream::Decoder::new(content)?)),
+        _ => Err(RPMError::UnknownCompressorType(compressor.to_string())),
+    }
+}
+
 pub(crate) struct CompressionDetails {
     pub(crate) compression_level: &'static str,
     pub(crate) compression_name: &'static str,
@@ -0,0 +1,194 @@
+//! Generate requires and provides from file contents,
+//! like the `elfdeps` and script generators of rpmbuild.
+
+use goblin::elf::header::ET_DYN;
+use goblin::elf::symver::{VER_FLG_BASE, VER_FLG_WEAK};
+use goblin::elf::Elf;
+
+use super::headers::{Dependency, FileMode};
+use crate::constants::*;
+
+/// The dependencies generated for a single file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct FileDependencies {
+    pub(crate) provides: Vec<Dependency>,
+    pub(crate) requires: Vec<Dependency>,
+}
+
+impl FileDependencies {
+    fn provide(&mut self, name: String) {
+        let dep = Dependency::new(name, RPMSENSE_FIND_PROVIDES, "".to_string());
+        if !self.provides.contains(&dep) {
+            self.provides.push(dep);
+        }
+    }
+
+    fn require(&mut self, name: String) {
+        let dep = Dependency::new(name, RPMSENSE_FIND_REQUIRES, "".to_string());
+        if !self.requires.contains(&dep) {
+            self.requires.push(dep);
+        }
+    }
+}
+
+/// Analyze a file, files which are neither ELF objects nor executable scripts have no dependencies.
+pub(crate) fn file_dependencies(content: &[u8], mode: FileMode) -> FileDependencies {
+    let executable = match mode {
+        FileMode::Regular { permissions } => permissions & 0o111 != 0,
+        _ => return FileDependencies::default(),
+    };
+    if content.starts_with(b"\x7fELF") {
+        elf_dependencies(content)
+    } else if executable && content.starts_with(b"#!") {
+        script_dependencies(content)
+    } else {
+        FileDependencies::default()
+    }
+}
+
+/// The capability name of a shared object, i.e. `libc.so.6(GLIBC_2.28)(64bit)`.
+fn soname_capability(soname: &str, version: Option<&str>, is_64: bool) -> String {
+    let marker = if is_64 { "(64bit)" } else { "" };
+    match version {
+        Some(version) => format!("{}({}){}", soname, version, marker),
+        None if is_64 => format!("{}(){}", soname, marker),
+        None => soname.to_string(),
+    }
+}
+
+fn elf_dependencies(content: &[u8]) -> FileDependencies {
+    let mut deps = FileDependencies::default();
+    // like rpmbuild, ignore what can not be parsed
+    let elf = match Elf::parse(content) {
+        Ok(elf) => elf,
+        Err(e) => {
+            log::debug!(
+                "skipping dependency generation for invalid ELF object: {}",
+                e
+            );
+            return deps;
+        }
+    };
+
+    if let (Some(soname), ET_DYN) = (elf.soname, elf.header.e_type) {
+        deps.provide(soname_capability(soname, None, elf.is_64));
+        if let Some(verdef) = &elf.verdef {
+            for def in verdef.iter() {
+                // the base definition is the soname itself
+                if def.vd_flags & VER_FLG_BASE != 0 {
+                    continue;
+                }
+                if let Some(version) = def
+                    .iter()
+                    .next()
+                    .and_then(|aux| elf.dynstrtab.get_at(aux.vda_name))
+                {
+                    deps.provide(soname_capability(soname, Some(version), elf.is_64));
+                }
+            }
+        }
+    }
+
+    for library in elf.libraries.iter() {
+        deps.require(soname_capability(library, None, elf.is_64));
+    }
+    if let Some(verneed) = &elf.verneed {
+        for need in verneed.iter() {
+            let library = match elf.dynstrtab.get_at(need.vn_file) {
+                Some(library) => library,
+                None => continue,
+            };
+            for aux in need.iter() {
+                if aux.vna_flags & VER_FLG_WEAK != 0 {
+                    continue;
+                }
+                if let Some(version) = elf.dynstrtab.get_at(aux.vna_name) {
+                    deps.require(soname_capability(library, Some(version), elf.is_64));
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// Require the interpreter of a `#!` script.
+fn script_dependencies(content: &[u8]) -> FileDependencies {
+    let mut deps = FileDependencies::default();
+    let line = content[2..]
+        .split(|c| *c == b'\n')
+        .next()
+        .unwrap_or_default();
+    let line = String::from_utf8_lossy(line);
+    if let Some(interpreter) = line.split_whitespace().next() {
+        if interpreter.starts_with('/') {
+            deps.require(interpreter.to_string());
+        }
+    }
+    deps
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_soname_capability() {
+        assert_eq!(
+            "libc.so.6(GLIBC_2.28)(64bit)",
+            soname_capability("libc.so.6", Some("GLIBC_2.28"), true)
+        );
+        assert_eq!(
+            "libc.so.6()(64bit)",
+            soname_capability("libc.so.6", None, true)
+        );
+        assert_eq!(
+            "libc.so.6(GLIBC_2.0)",
+            soname_capability("libc.so.6", Some("GLIBC_2.0"), false)
+        );
+        assert_eq!("libc.so.6", soname_capability("libc.so.6", None, false));
+    }
+
+    #[test]
+    fn test_script_dependencies() {
+        let script = b"#!/usr/bin/python3 -E\nprint('hello')\n";
+        assert_eq!(
+            vec![Dependency::new(
+                "/usr/bin/python3".to_string(),
+                RPMSENSE_FIND_REQUIRES,
+                "".to_string()
+            )],
+            file_dependencies(script, FileMode::regular(0o755)).requires
+        );
+        assert_eq!(
+            FileDependencies::default(),
+            file_dependencies(script, FileMode::regular(0o644))
+        );
+        assert_eq!(
+            FileDependencies::default(),
+            file_dependencies(b"#! env\n", FileMode::regular(0o755))
+        );
+    }
+
+    #[test]
+    fn test_elf_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+        // the test binary itself is a dynamically linked ELF executable
+        let content = std::fs::read(std::env::current_exe()?)?;
+        let deps = file_dependencies(&content, FileMode::regular(0o755));
+        if cfg!(all(
+            target_os = "linux",
+            target_env = "gnu",
+            target_pointer_width = "64"
+        )) {
+            assert!(deps
+                .requires
+                .iter()
+                .any(|dep| dep.name() == "libc.so.6()(64bit)"));
+            assert!(deps
+                .requires
+                .iter()
+                .any(|dep| dep.name().starts_with("libc.so.6(GLIBC_")));
+        }
+        assert!(deps.provides.is_empty());
+        Ok(())
+    }
+}
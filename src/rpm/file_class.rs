@@ -0,0 +1,140 @@
+//! Classify file contents for the `RPMTAG_FILECOLORS` and `RPMTAG_FILECLASS` tags,
+//! a small subset of what rpmbuild derives from libmagic.
+
+use super::headers::FileMode;
+use crate::constants::*;
+
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ELFDATA2MSB: u8 = 2;
+
+/// The color and class of a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileClass {
+    pub(crate) color: u32,
+    pub(crate) class: String,
+}
+
+/// Classify a file, the color is only set for ELF objects.
+pub(crate) fn classify(content: &[u8], mode: FileMode) -> FileClass {
+    let permissions = match mode {
+        FileMode::Regular { permissions } => permissions,
+        FileMode::Dir { .. } => return FileClass::new(RPMFC_BLACK, "directory".to_string()),
+        FileMode::Invalid { .. } => return FileClass::new(RPMFC_BLACK, String::new()),
+    };
+    if let Some(class) = elf_class(content) {
+        return class;
+    }
+    let class = if content.is_empty() {
+        "empty".to_string()
+    } else {
+        let encoding = if content.is_ascii() {
+            "ASCII"
+        } else if std::str::from_utf8(content).is_ok() {
+            "UTF-8 Unicode"
+        } else {
+            return FileClass::new(RPMFC_BLACK, "data".to_string());
+        };
+        let executable = permissions & 0o111 != 0;
+        match interpreter(content) {
+            Some(interpreter) if executable => {
+                format!("a {} script, {} text executable", interpreter, encoding)
+            }
+            _ => format!("{} text", encoding),
+        }
+    };
+    FileClass::new(RPMFC_BLACK, class)
+}
+
+impl FileClass {
+    fn new(color: u32, class: String) -> Self {
+        Self { color, class }
+    }
+}
+
+/// Read the identification and type of an ELF header, i.e. `ELF 64-bit LSB shared object`.
+fn elf_class(content: &[u8]) -> Option<FileClass> {
+    if content.len() < 18 || !content.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let (color, bits) = match content[4] {
+        ELFCLASS32 => (RPMFC_ELF32, 32),
+        ELFCLASS64 => (RPMFC_ELF64, 64),
+        _ => return None,
+    };
+    let (e_type, endianness) = match content[5] {
+        ELFDATA2LSB => (u16::from_le_bytes([content[16], content[17]]), "LSB"),
+        ELFDATA2MSB => (u16::from_be_bytes([content[16], content[17]]), "MSB"),
+        _ => return None,
+    };
+    let kind = match e_type {
+        1 => "relocatable",
+        2 => "executable",
+        3 => "shared object",
+        4 => "core file",
+        _ => "processor-specific",
+    };
+    Some(FileClass::new(
+        color,
+        format!("ELF {}-bit {} {}", bits, endianness, kind),
+    ))
+}
+
+/// The interpreter of a `#!` script, arguments are dropped.
+fn interpreter(content: &[u8]) -> Option<String> {
+    let line = content.strip_prefix(b"#!")?.split(|c| *c == b'\n').next()?;
+    let line = String::from_utf8_lossy(line);
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "/usr/bin/env" | "/bin/env" => words.next().map(str::to_owned),
+        interpreter => Some(interpreter.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(16, 0);
+        elf.extend_from_slice(&3u16.to_le_bytes());
+        assert_eq!(
+            FileClass::new(RPMFC_ELF64, "ELF 64-bit LSB shared object".to_string()),
+            classify(&elf, FileMode::regular(0o755))
+        );
+        elf[4] = 1;
+        elf[5] = 2;
+        elf[16..18].copy_from_slice(&2u16.to_be_bytes());
+        assert_eq!(
+            FileClass::new(RPMFC_ELF32, "ELF 32-bit MSB executable".to_string()),
+            classify(&elf, FileMode::regular(0o755))
+        );
+
+        let classify_str = |content: &[u8], mode| classify(content, mode).class;
+        assert_eq!("directory", classify_str(b"", FileMode::dir(0o755)));
+        assert_eq!("empty", classify_str(b"", FileMode::regular(0o644)));
+        assert_eq!(
+            "ASCII text",
+            classify_str(b"hello\n", FileMode::regular(0o644))
+        );
+        assert_eq!(
+            "UTF-8 Unicode text",
+            classify_str("grüezi\n".as_bytes(), FileMode::regular(0o644))
+        );
+        assert_eq!(
+            "data",
+            classify_str(b"\xff\xfe\x00", FileMode::regular(0o644))
+        );
+        assert_eq!(
+            "a python3 script, ASCII text executable",
+            classify_str(b"#!/usr/bin/env python3\n", FileMode::regular(0o755))
+        );
+        assert_eq!(
+            "ASCII text",
+            classify_str(b"#!/bin/sh\n", FileMode::regular(0o644))
+        );
+    }
+}
@@ -7,6 +7,7 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use crate::constants::{self, *};
 use chrono::offset::TimeZone;
 use num_traits::FromPrimitive;
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::fmt;
 use std::path::PathBuf;
@@ -34,7 +35,7 @@ impl<T> Tag for T where
 {
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Header<T: num::FromPrimitive> {
     pub(crate) index_header: IndexHeader,
     pub(crate) index_entries: Vec<IndexEntry<T>>,
@@ -303,22 +304,34 @@ where
 impl Header<IndexSignatureTag> {
     /// Create a new full signature header.
     ///
-    /// `size` is combined size of header, header store and the payload
+    /// `size` is combined size of header, header store and the payload,
+    /// `payload_size` the uncompressed size of the payload archive if known
     ///
     /// PGP and RSA tags expect signatures according to [RFC2440](https://tools.ietf.org/html/rfc2440)
     ///
     /// Please use the [`builder`](Self::builder()) which has modular and safe API.
     #[cfg(feature = "signature-meta")]
     pub(crate) fn new_signature_header(
-        size: i32,
+        size: u64,
+        payload_size: Option<u64>,
         md5sum: &[u8],
         sha1: String,
+        algorithm: crate::signature::algorithm::AlgorithmType,
         rsa_spanning_header: &[u8],
         rsa_spanning_header_and_archive: &[u8],
     ) -> Self {
-        SignatureHeaderBuilder::new()
+        let builder = SignatureHeaderBuilder::new();
+        let builder = match payload_size {
+            Some(payload_size) => builder.payload_size(payload_size),
+            None => builder,
+        };
+        builder
             .add_digest(sha1.as_str(), md5sum)
-            .add_signature(rsa_spanning_header, rsa_spanning_header_and_archive)
+            .add_signature_with_algorithm(
+                algorithm,
+                rsa_spanning_header,
+                rsa_spanning_header_and_archive,
+            )
             .build(size)
     }
 
@@ -390,6 +403,161 @@ impl Header<IndexSignatureTag> {
     pub fn get_file_ima_signature_length(&self) -> Result<i32, RPMError> {
         self.get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_FILESIGNATURE_LENGTH)
     }
+
+    /// Describe the OpenPGP signature stored in `tag`, without verifying it.
+    ///
+    /// Only the signature packet is parsed, so the returned key id and fingerprint
+    /// name the key the signature claims to be issued by.
+    #[cfg(feature = "signature-pgp")]
+    pub fn get_signature_info(
+        &self,
+        tag: IndexSignatureTag,
+    ) -> Result<crate::signature::SignatureInfo, RPMError> {
+        crate::signature::pgp::parse_signature_info(self.get_entry_binary_data(tag)?)
+    }
+
+    /// Describe all OpenPGP signatures of the package, stored in `RPMSIGTAG_RSA`,
+    /// `RPMSIGTAG_DSA`, `RPMSIGTAG_PGP` and `RPMSIGTAG_GPG`, without verifying them.
+    ///
+    /// Unsigned packages yield no signatures, a signature that cannot be parsed an error.
+    #[cfg(feature = "signature-pgp")]
+    pub fn get_signatures(&self) -> Result<Vec<crate::signature::PackageSignature>, RPMError> {
+        crate::signature::SIGNATURE_TAGS
+            .iter()
+            .filter(|(tag, _)| self.find_entry_or_err(tag).is_ok())
+            .map(|(tag, range)| {
+                Ok(crate::signature::PackageSignature {
+                    tag: *tag,
+                    range: *range,
+                    info: self.get_signature_info(*tag)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The base64 encoded fs-verity signatures, in the order of the files in the header.
+    #[inline]
+    pub fn get_file_verity_signatures(&self) -> Result<&[String], RPMError> {
+        self.get_entry_string_array_data(IndexSignatureTag::RPMSIGTAG_VERITYSIGNATURES)
+    }
+
+    /// The hash algorithm of the fs-verity Merkle trees, `FS_VERITY_HASH_ALG_SHA256`.
+    #[inline]
+    pub fn get_file_verity_signature_algorithm(&self) -> Result<i32, RPMError> {
+        self.get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_VERITYSIGNATUREALGO)
+    }
+
+    /// Append `entries` to the signature header.
+    pub(crate) fn with_entries(self, entries: Vec<IndexEntry<IndexSignatureTag>>) -> Self {
+        if entries.is_empty() {
+            return self;
+        }
+        let mut all_entries = self
+            .index_entries
+            .into_iter()
+            .filter(|entry| entry.tag != IndexSignatureTag::HEADER_SIGNATURES)
+            .collect::<Vec<_>>();
+        all_entries.extend(entries);
+        Self::from_entries(all_entries, IndexSignatureTag::HEADER_SIGNATURES)
+    }
+
+    /// The number of bytes the signature header takes within the file, including the alignment.
+    pub(crate) fn written_len(&self) -> usize {
+        let len = 16
+            + 16 * self.index_header.num_entries as usize
+            + self.index_header.header_size as usize;
+        len + (8 - len % 8) % 8
+    }
+
+    /// Pad the signature header with `RPMSIGTAG_RESERVEDSPACE`, so it takes `len` bytes within the file.
+    ///
+    /// Fails if the signature header without reserved space does not fit into `len` bytes.
+    pub(crate) fn with_reserved_space(self, len: usize) -> Result<Self, Self> {
+        let entries = self
+            .index_entries
+            .into_iter()
+            .filter(|entry| {
+                entry.tag != IndexSignatureTag::HEADER_SIGNATURES
+                    && entry.tag != IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE
+            })
+            .collect::<Vec<_>>();
+        let unpadded = Self::from_entries(entries, IndexSignatureTag::HEADER_SIGNATURES);
+
+        // the reserved space is binary data without alignment, appended in front of the
+        // region trailer, padding up to `len` removes the need for alignment at the end
+        let fixed_len = 16
+            + 16 * (unpadded.index_header.num_entries as usize + 1)
+            + unpadded.index_header.header_size as usize;
+        if len % 8 != 0 || len <= fixed_len {
+            return Err(unpadded);
+        }
+
+        let mut entries = unpadded.index_entries;
+        entries.remove(0);
+        entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE,
+            0i32, // filled by from_entries
+            IndexData::Bin(vec![0u8; len - fixed_len]),
+        ));
+        Ok(Self::from_entries(
+            entries,
+            IndexSignatureTag::HEADER_SIGNATURES,
+        ))
+    }
+
+    /// Pad the signature header to the size it would have unsigned with
+    /// [`SIGNATURE_RESERVED_SPACE`], so the signatures take their room from
+    /// the reserved space, like `rpmsign` does.
+    ///
+    /// Signatures beyond the reserved space leave the signature header unpadded.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn with_signature_reserved_space(self) -> Self {
+        let mut unsigned = self
+            .index_entries
+            .iter()
+            .filter(|entry| {
+                !matches!(
+                    entry.tag,
+                    IndexSignatureTag::HEADER_SIGNATURES
+                        | IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE
+                        | IndexSignatureTag::RPMSIGTAG_RSA
+                        | IndexSignatureTag::RPMSIGTAG_PGP
+                        | IndexSignatureTag::RPMSIGTAG_DSA
+                        | IndexSignatureTag::RPMSIGTAG_GPG
+                )
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        unsigned.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE,
+            0i32, // filled by from_entries
+            IndexData::Bin(vec![0u8; SIGNATURE_RESERVED_SPACE]),
+        ));
+        let len = Self::from_entries(unsigned, IndexSignatureTag::HEADER_SIGNATURES).written_len();
+        match self.with_reserved_space(len) {
+            Ok(signature) | Err(signature) => signature,
+        }
+    }
+
+    /// The combined size of the header and the payload.
+    pub fn get_size(&self) -> Result<u64, RPMError> {
+        self.get_entry_i64_data(IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE)
+            .map(|size| size as u64)
+            .or_else(|_| {
+                self.get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_SIZE)
+                    .map(|size| size as u32 as u64)
+            })
+    }
+
+    /// The uncompressed size of the payload archive, including the cpio headers.
+    pub fn get_payload_size(&self) -> Result<u64, RPMError> {
+        self.get_entry_i64_data(IndexSignatureTag::RPMSIGTAG_LONGARCHIVESIZE)
+            .map(|size| size as u64)
+            .or_else(|_| {
+                self.get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE)
+                    .map(|size| size as u32 as u64)
+            })
+    }
 }
 
 impl Header<IndexTag> {
@@ -433,11 +601,253 @@ impl Header<IndexTag> {
         self.get_entry_string_data(IndexTag::RPMTAG_ARCH)
     }
 
+    #[inline]
+    pub fn get_summary(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_SUMMARY)
+    }
+
+    #[inline]
+    pub fn get_description(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_DESCRIPTION)
+    }
+
+    #[inline]
+    pub fn get_license(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_LICENSE)
+    }
+
+    #[inline]
+    pub fn get_group(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_GROUP)
+    }
+
+    #[inline]
+    pub fn get_url(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_URL)
+    }
+
+    #[inline]
+    pub fn get_vendor(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_VENDOR)
+    }
+
+    #[inline]
+    pub fn get_packager(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_PACKAGER)
+    }
+
+    #[inline]
+    pub fn get_distribution(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_DISTRIBUTION)
+    }
+
+    #[inline]
+    pub fn get_bug_url(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_BUGURL)
+    }
+
+    #[inline]
+    pub fn get_vcs(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_VCS)
+    }
+
+    #[inline]
+    pub fn get_build_host(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_BUILDHOST)
+    }
+
+    #[inline]
+    pub fn get_source_rpm(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_SOURCERPM)
+    }
+
+    #[inline]
+    pub fn get_dist_tag(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_DISTTAG)
+    }
+
+    #[inline]
+    pub fn get_modularity_label(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_data(IndexTag::RPMTAG_MODULARITYLABEL)
+    }
+
+    #[inline]
+    pub fn get_build_time(&self) -> Result<i32, RPMError> {
+        self.get_entry_i32_data(IndexTag::RPMTAG_BUILDTIME)
+    }
+
+    /// The combined size of all files once installed.
+    pub fn get_installed_size(&self) -> Result<u64, RPMError> {
+        self.get_entry_i64_data(IndexTag::RPMTAG_LONGSIZE)
+            .map(|size| size as u64)
+            .or_else(|_| {
+                self.get_entry_i32_data(IndexTag::RPMTAG_SIZE)
+                    .map(|size| size as u32 as u64)
+            })
+    }
+
+    /// The prefixes a relocatable package can be moved from.
+    #[inline]
+    pub fn get_prefixes(&self) -> Result<&[String], RPMError> {
+        self.get_entry_string_array_data(IndexTag::RPMTAG_PREFIXES)
+    }
+
+    /// The prefixes an installed relocatable package was moved to, as recorded by rpm.
+    #[inline]
+    pub fn get_install_prefixes(&self) -> Result<&[String], RPMError> {
+        self.get_entry_string_array_data(IndexTag::RPMTAG_INSTPREFIXES)
+    }
+
+    /// Whether this is the header of a source package.
+    pub fn is_source_package(&self) -> bool {
+        self.find_entry_or_err(&IndexTag::RPMTAG_SOURCEPACKAGE)
+            .is_ok()
+    }
+
+    /// The source file names of a source package, `Source0` first.
+    #[inline]
+    pub fn get_sources(&self) -> Result<&[String], RPMError> {
+        self.get_entry_string_array_data(IndexTag::RPMTAG_SOURCE)
+    }
+
+    /// The patch file names of a source package, `Patch0` first.
+    #[inline]
+    pub fn get_patches(&self) -> Result<&[String], RPMError> {
+        self.get_entry_string_array_data(IndexTag::RPMTAG_PATCH)
+    }
+
     #[inline]
     pub fn get_install_time(&self) -> Result<i64, RPMError> {
         self.get_entry_i64_data(IndexTag::RPMTAG_INSTALLTIME)
     }
 
+    /// The scriptlet of the given type including its interpreter.
+    pub fn get_scriptlet(&self, ty: ScriptletType) -> Result<Scriptlet, RPMError> {
+        let tags = ty.index_tags();
+        let script = self.get_entry_string_data(tags.script)?;
+        // the interpreter is either a plain string or an array with arguments
+        let program = match self.find_entry_or_err(&tags.program) {
+            Ok(entry) => match &entry.data {
+                IndexData::StringTag(prog) => vec![prog.to_owned()],
+                IndexData::StringArray(prog) => prog.to_owned(),
+                data => {
+                    return Err(RPMError::UnexpectedTagDataType {
+                        expected_data_type: "string or string array",
+                        actual_data_type: data.to_string(),
+                        tag: entry.tag.to_string(),
+                    })
+                }
+            },
+            Err(_) => vec![DEFAULT_SCRIPTLET_INTERPRETER.to_string()],
+        };
+        let flags = self.get_entry_i32_data(tags.flags).unwrap_or_default();
+        Ok(Scriptlet::new(script).prog(program).flags(flags as u32))
+    }
+
+    /// All scriptlets present in the package, in the order they run.
+    pub fn get_scriptlets(&self) -> Result<Vec<(ScriptletType, Scriptlet)>, RPMError> {
+        ScriptletType::ALL
+            .iter()
+            .filter(|ty| self.find_entry_or_err(&ty.index_tags().script).is_ok())
+            .map(|ty| Ok((*ty, self.get_scriptlet(*ty)?)))
+            .collect()
+    }
+
+    /// All package triggers, each with the conditions it reacts to.
+    pub fn get_triggers(&self) -> Result<Vec<Trigger>, RPMError> {
+        Ok(self
+            .get_triggers_with_priorities(&TriggerIndexTags::PACKAGE)?
+            .into_iter()
+            .map(|(trigger, _)| trigger)
+            .collect())
+    }
+
+    /// Extract the file triggers of both scopes, package file triggers first.
+    pub fn get_file_triggers(&self) -> Result<Vec<FileTrigger>, RPMError> {
+        let mut file_triggers = Vec::new();
+        for scope in [FileTriggerScope::Package, FileTriggerScope::Transaction] {
+            let triggers = self.get_triggers_with_priorities(TriggerIndexTags::for_scope(scope))?;
+            for (trigger, priority) in triggers {
+                let mut file_trigger =
+                    FileTrigger::new(trigger.ty, scope, trigger.scriptlet).priority(priority);
+                file_trigger.prefixes = trigger
+                    .conditions
+                    .into_iter()
+                    .map(|condition| condition.dep_name)
+                    .collect();
+                file_triggers.push(file_trigger);
+            }
+        }
+        Ok(file_triggers)
+    }
+
+    fn get_triggers_with_priorities(
+        &self,
+        tags: &TriggerIndexTags,
+    ) -> Result<Vec<(Trigger, u32)>, RPMError> {
+        let scripts = match self.get_entry_string_array_data(tags.scripts) {
+            Ok(scripts) => scripts,
+            Err(RPMError::TagNotFound(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let progs = self.get_entry_string_array_data(tags.progs)?;
+        let script_flags = self
+            .get_entry_i32_array_data(tags.script_flags)
+            .unwrap_or_else(|_| vec![0; scripts.len()]);
+        let names = self.get_entry_string_array_data(tags.names)?;
+        let versions = self.get_entry_string_array_data(tags.versions)?;
+        let flags = self.get_entry_i32_array_data(tags.flags)?;
+        let indexes = self.get_entry_i32_array_data(tags.indexes)?;
+        let priorities = match tags.priorities {
+            Some(tag) => self.get_entry_i32_array_data(tag)?,
+            None => Vec::new(),
+        };
+
+        let mut triggers: Vec<Option<Trigger>> = vec![None; scripts.len()];
+        for (name, version, flags, index) in itertools::multizip((names, versions, flags, indexes))
+        {
+            let flags = flags as u32;
+            let ty = TriggerType::from_sense(flags).ok_or_else(|| {
+                RPMError::InvalidTagValueEnumVariant {
+                    tag: tags.flags.to_string(),
+                    variant: flags,
+                }
+            })?;
+            let trigger = triggers
+                .get_mut(index as usize)
+                .ok_or_else(|| RPMError::InvalidTagIndex {
+                    tag: tags.indexes.to_string(),
+                    index: index as u32,
+                    bound: scripts.len() as u32,
+                })?
+                .get_or_insert_with(|| {
+                    let scriptlet = Scriptlet::new(scripts[index as usize].as_str())
+                        .prog(vec![progs
+                            .get(index as usize)
+                            .map(String::as_str)
+                            .unwrap_or(DEFAULT_SCRIPTLET_INTERPRETER)])
+                        .flags(script_flags.get(index as usize).copied().unwrap_or(0) as u32);
+                    Trigger::new(ty, scriptlet)
+                });
+            trigger.conditions.push(Dependency::new(
+                name.to_owned(),
+                flags & !ty.sense(),
+                version.to_owned(),
+            ));
+        }
+        Ok(triggers
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, trigger)| {
+                let priority = priorities
+                    .get(index)
+                    .map(|priority| *priority as u32)
+                    .unwrap_or(DEFAULT_FILE_TRIGGER_PRIORITY);
+                trigger.map(|trigger| (trigger, priority))
+            })
+            .collect())
+    }
+
     /// Extract a the set of contained file names.
     pub fn get_file_paths(&self) -> Result<Vec<PathBuf>, RPMError> {
         // reconstruct the messy de-constructed paths
@@ -445,26 +855,31 @@ impl Header<IndexTag> {
         let biject = self.get_entry_i32_array_data(IndexTag::RPMTAG_DIRINDEXES)?;
         let dirs = self.get_entry_string_array_data(IndexTag::RPMTAG_DIRNAMES)?;
 
+        if biject.len() != base.len() {
+            return Err(RPMError::InvalidTagArrayLength {
+                tag: IndexTag::RPMTAG_DIRINDEXES.to_string(),
+                expected: base.len(),
+                actual: biject.len(),
+            });
+        }
+
         let n = dirs.len();
-        let v = base
-            .iter()
-            .zip(biject.into_iter())
-            .try_fold::<Vec<PathBuf>, _, _>(
-                Vec::<PathBuf>::with_capacity(base.len()),
-                |mut acc, item| {
-                    let (base, dir_index) = item;
-                    if let Some(dir) = dirs.get(dir_index as usize) {
-                        acc.push(PathBuf::from(dir).join(base));
-                        Ok(acc)
-                    } else {
-                        Err(RPMError::InvalidTagIndex {
-                            tag: IndexTag::RPMTAG_DIRINDEXES.to_string(),
-                            index: dir_index as u32,
-                            bound: n as u32,
-                        })
-                    }
-                },
-            )?;
+        let v = base.iter().zip(biject).try_fold::<Vec<PathBuf>, _, _>(
+            Vec::<PathBuf>::with_capacity(base.len()),
+            |mut acc, item| {
+                let (base, dir_index) = item;
+                if let Some(dir) = dirs.get(dir_index as usize) {
+                    acc.push(PathBuf::from(dir).join(base));
+                    Ok(acc)
+                } else {
+                    Err(RPMError::InvalidTagIndex {
+                        tag: IndexTag::RPMTAG_DIRINDEXES.to_string(),
+                        index: dir_index as u32,
+                        bound: n as u32,
+                    })
+                }
+            },
+        )?;
         Ok(v)
     }
 
@@ -484,6 +899,93 @@ impl Header<IndexTag> {
             })
     }
 
+    /// The digest of the compressed payload from `RPMTAG_PAYLOADDIGEST`.
+    ///
+    /// A missing `RPMTAG_PAYLOADDIGESTALGO` means SHA-256, like in librpm.
+    pub fn get_payload_digest(&self) -> Result<FileDigest, RPMError> {
+        let algorithm = match self.get_entry_i32_data(IndexTag::RPMTAG_PAYLOADDIGESTALGO) {
+            Ok(x) => FileDigestAlgorithm::from_i32(x).ok_or_else(|| {
+                RPMError::InvalidTagValueEnumVariant {
+                    tag: IndexTag::RPMTAG_PAYLOADDIGESTALGO.to_string(),
+                    variant: x as u32,
+                }
+            })?,
+            Err(RPMError::TagNotFound(_)) => FileDigestAlgorithm::Sha2_256,
+            Err(e) => return Err(e),
+        };
+        let digests = self.get_entry_string_array_data(IndexTag::RPMTAG_PAYLOADDIGEST)?;
+        let digest = digests.first().ok_or_else(|| RPMError::InvalidTagIndex {
+            tag: IndexTag::RPMTAG_PAYLOADDIGEST.to_string(),
+            index: 0,
+            bound: 0,
+        })?;
+        FileDigest::load_from_str(algorithm, digest)
+    }
+
+    /// The extended attributes of each file, resolved through `RPMTAG_XATTRSDICT`.
+    ///
+    /// rpm reserves the tags without defining a format, only packages built by fez
+    /// carry them in a format this can decode. Dictionary entries in any other format
+    /// are read as files without extended attributes.
+    pub fn get_file_xattrs(&self) -> Result<Vec<BTreeMap<String, Vec<u8>>>, RPMError> {
+        let dict = self.get_entry_string_array_data(IndexTag::RPMTAG_XATTRSDICT)?;
+        let indexes = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILEXATTRSX)?;
+        let dict = dict
+            .iter()
+            .map(|entry| decode_xattrs(entry).unwrap_or_default())
+            .collect::<Vec<_>>();
+        indexes
+            .into_iter()
+            .map(|index| {
+                dict.get(index as usize)
+                    .cloned()
+                    .ok_or_else(|| RPMError::InvalidTagIndex {
+                        tag: IndexTag::RPMTAG_FILEXATTRSX.to_string(),
+                        index: index as u32,
+                        bound: dict.len() as u32,
+                    })
+            })
+            .collect()
+    }
+
+    /// Get the class of each file, resolved from `RPMTAG_CLASSDICT`.
+    pub fn get_file_classes(&self) -> Result<Vec<String>, RPMError> {
+        let indexes = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILECLASS)?;
+        let dict = self.get_entry_string_array_data(IndexTag::RPMTAG_CLASSDICT)?;
+        indexes
+            .into_iter()
+            .map(|index| {
+                dict.get(index as usize)
+                    .map(|class| class.to_string())
+                    .ok_or_else(|| RPMError::InvalidTagIndex {
+                        tag: IndexTag::RPMTAG_FILECLASS.to_string(),
+                        index: index as u32,
+                        bound: dict.len() as u32,
+                    })
+            })
+            .collect()
+    }
+
+    /// The size of each file, from `RPMTAG_LONGFILESIZES` for packages with large files.
+    pub(crate) fn get_file_sizes(&self) -> Result<Vec<u64>, RPMError> {
+        self.get_entry_i64_array_data(IndexTag::RPMTAG_LONGFILESIZES)
+            .map(|file_sizes| {
+                file_sizes
+                    .into_iter()
+                    .map(|file_size| file_size as u64)
+                    .collect()
+            })
+            .or_else(|_e| {
+                self.get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)
+                    .map(|file_sizes| {
+                        file_sizes
+                            .into_iter()
+                            .map(|file_size| file_size as u32 as u64)
+                            .collect()
+                    })
+            })
+    }
+
     /// Extract a the set of contained file names including the additional metadata.
     pub fn get_file_entries(&self) -> Result<Vec<FileEntry>, RPMError> {
         // rpm does not encode it, if it is the default md5
@@ -494,24 +996,68 @@ impl Header<IndexTag> {
         let groups = self.get_entry_string_array_data(IndexTag::RPMTAG_FILEGROUPNAME)?;
         let digests = self.get_entry_string_array_data(IndexTag::RPMTAG_FILEDIGESTS)?;
         let mtimes = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILEMTIMES)?;
-        let sizes = self
-            .get_entry_i64_array_data(IndexTag::RPMTAG_LONGFILESIZES)
-            .or_else(|_e| {
-                self.get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)
-                    .map(|file_sizes| {
-                        file_sizes
-                            .into_iter()
-                            .map(|file_size| file_size as _)
-                            .collect::<Vec<i64>>()
-                    })
-            })?;
+        let sizes = self.get_file_sizes()?;
         let flags = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILEFLAGS)?;
-        // @todo
-        // let caps = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILECAPS)?;
-
         let paths = self.get_file_paths()?;
         let n = paths.len();
 
+        // all are optional, most packages do not carry them
+        let optional_strings = |tag: IndexTag| {
+            self.get_entry_string_array_data(tag)
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|value| Some(value.to_owned()).filter(|v| !v.is_empty()))
+                        .collect::<Vec<_>>()
+                })
+                .or_else(|e| match e {
+                    RPMError::TagNotFound(_) => Ok(vec![None; n]),
+                    e => Err(e),
+                })
+        };
+        let contexts = optional_strings(IndexTag::RPMTAG_FILECONTEXTS)?;
+        let caps = optional_strings(IndexTag::RPMTAG_FILECAPS)?;
+        let xattrs = self.get_file_xattrs().or_else(|e| match e {
+            RPMError::TagNotFound(_) => Ok(vec![BTreeMap::new(); n]),
+            e => Err(e),
+        })?;
+        let colors = self
+            .get_entry_i32_array_data(IndexTag::RPMTAG_FILECOLORS)
+            .map(|colors| colors.into_iter().map(|color| color as u32).collect())
+            .or_else(|e| match e {
+                RPMError::TagNotFound(_) => Ok(vec![RPMFC_BLACK; n]),
+                e => Err(e),
+            })?;
+        let classes = self.get_file_classes().or_else(|e| match e {
+            RPMError::TagNotFound(_) => Ok(vec![String::new(); n]),
+            e => Err(e),
+        })?;
+
+        // multizip stops at the shortest array, so a malformed header
+        // would silently drop files instead of failing
+        for (tag, len) in [
+            (IndexTag::RPMTAG_FILEUSERNAME, users.len()),
+            (IndexTag::RPMTAG_FILEGROUPNAME, groups.len()),
+            (IndexTag::RPMTAG_FILEMODES, modes.len()),
+            (IndexTag::RPMTAG_FILEDIGESTS, digests.len()),
+            (IndexTag::RPMTAG_FILEMTIMES, mtimes.len()),
+            (IndexTag::RPMTAG_FILESIZES, sizes.len()),
+            (IndexTag::RPMTAG_FILEFLAGS, flags.len()),
+            (IndexTag::RPMTAG_FILECONTEXTS, contexts.len()),
+            (IndexTag::RPMTAG_FILECAPS, caps.len()),
+            (IndexTag::RPMTAG_FILEXATTRSX, xattrs.len()),
+            (IndexTag::RPMTAG_FILECOLORS, colors.len()),
+            (IndexTag::RPMTAG_FILECLASS, classes.len()),
+        ] {
+            if len != n {
+                return Err(RPMError::InvalidTagArrayLength {
+                    tag: tag.to_string(),
+                    expected: n,
+                    actual: len,
+                });
+            }
+        }
+
         let v = itertools::multizip((
             paths.into_iter(),
             users,
@@ -521,10 +1067,28 @@ impl Header<IndexTag> {
             mtimes,
             sizes,
             flags,
+            contexts.into_iter().zip(caps),
+            xattrs,
+            colors,
+            classes,
         ))
         .try_fold::<Vec<FileEntry>, _, Result<_, RPMError>>(
             Vec::with_capacity(n),
-            |mut acc, (path, user, group, mode, digest, mtime, size, flags)| {
+            |mut acc,
+             (
+                path,
+                user,
+                group,
+                mode,
+                digest,
+                mtime,
+                size,
+                flags,
+                (selinux_context, caps),
+                xattrs,
+                color,
+                class,
+            )| {
                 let digest = if digest.is_empty() {
                     None
                 } else {
@@ -538,10 +1102,15 @@ impl Header<IndexTag> {
                         group: group.to_owned(),
                     },
                     mode: mode.into(),
-                    modified_at: utc.timestamp(mtime as i64, 0u32),
+                    modified_at: utc.timestamp_opt(mtime as i64, 0u32).unwrap(),
                     digest,
                     category: FileCategory::from_i32(flags).unwrap_or_default(),
                     size: size as usize,
+                    selinux_context,
+                    xattrs,
+                    caps,
+                    color,
+                    class,
                 });
                 Ok(acc)
             },
@@ -574,7 +1143,7 @@ impl Default for FileCategory {
 }
 
 #[repr(i32)]
-#[derive(Debug, Clone, Copy, enum_primitive_derive::Primitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, enum_primitive_derive::Primitive)]
 pub enum FileDigestAlgorithm {
     // broken and very broken
     Md5 = constants::PGPHASHALGO_MD5,
@@ -600,14 +1169,87 @@ impl Default for FileDigestAlgorithm {
     }
 }
 
+impl FileDigestAlgorithm {
+    /// The length of a digest in bytes.
+    pub fn digest_len(self) -> usize {
+        match self {
+            Self::Md5 | Self::Md2 => 16,
+            Self::Sha1 | Self::Haval_5_160 | Self::Ripemd160 => 20,
+            Self::Tiger192 => 24,
+            Self::Sha2_224 => 28,
+            Self::Sha2_256 => 32,
+            Self::Sha2_384 => 48,
+            Self::Sha2_512 => 64,
+        }
+    }
+
+    /// Calculate the digest of a file, only MD5, SHA-1 and SHA-2 can be calculated.
+    pub(crate) fn digest(self, content: &[u8]) -> Result<Vec<u8>, RPMError> {
+        self.digest_parts(&[content])
+    }
+
+    /// Calculate the digest of consecutive parts without concatenating them.
+    pub(crate) fn digest_parts(self, parts: &[&[u8]]) -> Result<Vec<u8>, RPMError> {
+        fn digest<D: sha2::Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+        Ok(match self {
+            Self::Md5 => digest::<md5::Md5>(parts),
+            Self::Sha1 => digest::<sha1::Sha1>(parts),
+            Self::Sha2_224 => digest::<sha2::Sha224>(parts),
+            Self::Sha2_256 => digest::<sha2::Sha256>(parts),
+            Self::Sha2_384 => digest::<sha2::Sha384>(parts),
+            Self::Sha2_512 => digest::<sha2::Sha512>(parts),
+            algorithm => return Err(RPMError::UnsupportedFileDigestAlgorithm(algorithm)),
+        })
+    }
+
+    /// Calculate the digest of everything `reader` yields, without holding it in memory.
+    pub(crate) fn digest_reader<R: std::io::Read>(
+        self,
+        mut reader: R,
+    ) -> Result<Vec<u8>, RPMError> {
+        fn digest<D: sha2::Digest, R: std::io::Read>(reader: &mut R) -> Result<Vec<u8>, RPMError> {
+            let mut hasher = D::new();
+            let mut buf = [0u8; 64 * 1024];
+            loop {
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+            Ok(hasher.finalize().to_vec())
+        }
+        match self {
+            Self::Md5 => digest::<md5::Md5, R>(&mut reader),
+            Self::Sha1 => digest::<sha1::Sha1, R>(&mut reader),
+            Self::Sha2_224 => digest::<sha2::Sha224, R>(&mut reader),
+            Self::Sha2_256 => digest::<sha2::Sha256, R>(&mut reader),
+            Self::Sha2_384 => digest::<sha2::Sha384, R>(&mut reader),
+            Self::Sha2_512 => digest::<sha2::Sha512, R>(&mut reader),
+            algorithm => Err(RPMError::UnsupportedFileDigestAlgorithm(algorithm)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum FileDigest {
     Md5(Vec<u8>),
+    Sha1(Vec<u8>),
+    Md2(Vec<u8>),
+    #[allow(non_camel_case_types)]
+    Haval_5_160(Vec<u8>),
+    Ripemd160(Vec<u8>),
+    Tiger192(Vec<u8>),
     Sha2_256(Vec<u8>),
     Sha2_384(Vec<u8>),
     Sha2_512(Vec<u8>),
     Sha2_224(Vec<u8>),
-    // @todo unsupported other types for now
 }
 
 impl FileDigest {
@@ -616,16 +1258,42 @@ impl FileDigest {
         stringly_data: impl AsRef<str>,
     ) -> Result<Self, RPMError> {
         let hex: Vec<u8> = hex::decode(stringly_data.as_ref())?;
+        if hex.len() != algorithm.digest_len() {
+            return Err(RPMError::InvalidFileDigestLength {
+                algorithm,
+                expected: algorithm.digest_len(),
+                actual: hex.len(),
+            });
+        }
         Ok(match algorithm {
-            FileDigestAlgorithm::Md5 if hex.len() == 16 => FileDigest::Md5(hex),
-            FileDigestAlgorithm::Sha2_256 if hex.len() == 32 => FileDigest::Sha2_256(hex),
-            FileDigestAlgorithm::Sha2_224 if hex.len() == 30 => FileDigest::Sha2_224(hex),
-            FileDigestAlgorithm::Sha2_384 if hex.len() == 48 => FileDigest::Sha2_384(hex),
-            FileDigestAlgorithm::Sha2_512 if hex.len() == 64 => FileDigest::Sha2_512(hex),
-            // @todo disambiguate mismatch of length from unsupported algorithm
-            digest_algo => return Err(RPMError::UnsupportedFileDigestAlgorithm(digest_algo)),
+            FileDigestAlgorithm::Md5 => FileDigest::Md5(hex),
+            FileDigestAlgorithm::Sha1 => FileDigest::Sha1(hex),
+            FileDigestAlgorithm::Md2 => FileDigest::Md2(hex),
+            FileDigestAlgorithm::Haval_5_160 => FileDigest::Haval_5_160(hex),
+            FileDigestAlgorithm::Ripemd160 => FileDigest::Ripemd160(hex),
+            FileDigestAlgorithm::Tiger192 => FileDigest::Tiger192(hex),
+            FileDigestAlgorithm::Sha2_256 => FileDigest::Sha2_256(hex),
+            FileDigestAlgorithm::Sha2_224 => FileDigest::Sha2_224(hex),
+            FileDigestAlgorithm::Sha2_384 => FileDigest::Sha2_384(hex),
+            FileDigestAlgorithm::Sha2_512 => FileDigest::Sha2_512(hex),
         })
     }
+
+    /// The algorithm the digest was calculated with.
+    pub fn algorithm(&self) -> FileDigestAlgorithm {
+        match self {
+            FileDigest::Md5(_) => FileDigestAlgorithm::Md5,
+            FileDigest::Sha1(_) => FileDigestAlgorithm::Sha1,
+            FileDigest::Md2(_) => FileDigestAlgorithm::Md2,
+            FileDigest::Haval_5_160(_) => FileDigestAlgorithm::Haval_5_160,
+            FileDigest::Ripemd160(_) => FileDigestAlgorithm::Ripemd160,
+            FileDigest::Tiger192(_) => FileDigestAlgorithm::Tiger192,
+            FileDigest::Sha2_256(_) => FileDigestAlgorithm::Sha2_256,
+            FileDigest::Sha2_384(_) => FileDigestAlgorithm::Sha2_384,
+            FileDigest::Sha2_512(_) => FileDigestAlgorithm::Sha2_512,
+            FileDigest::Sha2_224(_) => FileDigestAlgorithm::Sha2_224,
+        }
+    }
 }
 
 /// User facing accessor type for a file entry with contextual information
@@ -643,8 +1311,17 @@ pub struct FileEntry {
     pub size: usize,
     /// Categorizes the file or directory into three groups.
     pub category: FileCategory,
-    // @todo SELinux context? how is that done?
     pub digest: Option<FileDigest>,
+    /// The SELinux context the file is labeled with, if any.
+    pub selinux_context: Option<String>,
+    /// Extended attributes set on the file, fez-only, see [`types::RPMFileOptionsBuilder::xattr`].
+    pub xattrs: BTreeMap<String, Vec<u8>>,
+    /// The POSIX file capabilities granted on installation, i.e. `cap_net_raw=ep`.
+    pub caps: Option<String>,
+    /// The ELF class of the file, `RPMFC_ELF32`, `RPMFC_ELF64` or `RPMFC_BLACK` for anything else.
+    pub color: u32,
+    /// A description of the file contents, i.e. `ELF 64-bit LSB shared object` or `directory`.
+    pub class: String,
 }
 
 fn parse_entry_data_number<'a, T, E, F>(
@@ -712,19 +1389,122 @@ mod tests2 {
         };
 
         let built = Header::<IndexSignatureTag>::new_signature_header(
-            size,
+            size as u64,
+            None,
             md5sum,
             sha1,
+            crate::signature::algorithm::AlgorithmType::RSA,
             rsa_spanning_header,
             rsa_spanning_header_and_archive,
         );
 
         assert_eq!(built, truth);
     }
+
+    #[test]
+    fn file_digest_lengths() -> Result<(), RPMError> {
+        let sha224 = "d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f";
+        assert_eq!(
+            FileDigestAlgorithm::Sha2_224,
+            FileDigest::load_from_str(FileDigestAlgorithm::Sha2_224, sha224)?.algorithm()
+        );
+        let sha1 = "da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        assert_eq!(
+            FileDigest::Sha1(hex::decode(sha1)?),
+            FileDigest::load_from_str(FileDigestAlgorithm::Sha1, sha1)?
+        );
+        assert!(matches!(
+            FileDigest::load_from_str(FileDigestAlgorithm::Sha2_256, sha1),
+            Err(RPMError::InvalidFileDigestLength {
+                expected: 32,
+                actual: 20,
+                ..
+            })
+        ));
+        assert_eq!(
+            sha224,
+            hex::encode(FileDigestAlgorithm::Sha2_224.digest(b"")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn file_entries_array_lengths() {
+        let header = |contexts: Vec<String>| {
+            let strings =
+                |v: &[&str]| IndexData::StringArray(v.iter().map(|s| s.to_string()).collect());
+            let entries = vec![
+                IndexEntry::new(IndexTag::RPMTAG_FILESIZES, 0, IndexData::Int32(vec![1, 2])),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_FILEMODES,
+                    0,
+                    IndexData::Int16(vec![0o100644_u32 as i16, 0o100644_u32 as i16]),
+                ),
+                IndexEntry::new(IndexTag::RPMTAG_FILEMTIMES, 0, IndexData::Int32(vec![0, 0])),
+                IndexEntry::new(IndexTag::RPMTAG_FILEDIGESTS, 0, strings(&["", ""])),
+                IndexEntry::new(IndexTag::RPMTAG_FILEFLAGS, 0, IndexData::Int32(vec![0, 0])),
+                IndexEntry::new(IndexTag::RPMTAG_FILEUSERNAME, 0, strings(&["root", "root"])),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_FILEGROUPNAME,
+                    0,
+                    strings(&["root", "root"]),
+                ),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_FILECONTEXTS,
+                    0,
+                    IndexData::StringArray(contexts),
+                ),
+                IndexEntry::new(IndexTag::RPMTAG_DIRINDEXES, 0, IndexData::Int32(vec![0, 0])),
+                IndexEntry::new(IndexTag::RPMTAG_BASENAMES, 0, strings(&["a", "b"])),
+                IndexEntry::new(IndexTag::RPMTAG_DIRNAMES, 0, strings(&["/"])),
+            ];
+            Header::<IndexTag>::from_entries(entries, IndexTag::RPMTAG_HEADERIMMUTABLE)
+        };
+
+        let context = "system_u:object_r:etc_t:s0".to_owned();
+        let entries = header(vec![context.clone(), String::new()])
+            .get_file_entries()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].selinux_context.as_deref(),
+            Some(context.as_str())
+        );
+        assert_eq!(entries[1].selinux_context, None);
+
+        assert!(matches!(
+            header(vec![context]).get_file_entries(),
+            Err(RPMError::InvalidTagArrayLength {
+                expected: 2,
+                actual: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn undecodable_file_xattrs() {
+        let entries = vec![
+            IndexEntry::new(
+                IndexTag::RPMTAG_XATTRSDICT,
+                0,
+                IndexData::StringArray(vec!["user.a=01".to_owned(), "\x01user.b".to_owned()]),
+            ),
+            IndexEntry::new(
+                IndexTag::RPMTAG_FILEXATTRSX,
+                0,
+                IndexData::Int32(vec![1, 0]),
+            ),
+        ];
+        let header = Header::<IndexTag>::from_entries(entries, IndexTag::RPMTAG_HEADERIMMUTABLE);
+        let xattrs = header.get_file_xattrs().unwrap();
+        assert!(xattrs[0].is_empty());
+        assert_eq!(xattrs[1].get("user.a"), Some(&vec![1u8]));
+    }
 }
 
 /// A header keeping track of all other headerr records.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct IndexHeader {
     /// rpm specific magic header
     pub(crate) magic: [u8; 3],
@@ -804,7 +1584,7 @@ impl IndexHeader {
 }
 
 /// A singel entry within the [`IndexHeader`](self::IndexHeader)
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct IndexEntry<T: num::FromPrimitive> {
     pub(crate) tag: T,
     pub(crate) data: IndexData,
@@ -884,7 +1664,7 @@ impl<T: num::FromPrimitive + num::ToPrimitive + fmt::Debug + TypeName> IndexEntr
 }
 
 /// Data as present in a [`IndexEntry`](self::IndexEntry) .
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum IndexData {
     Null,
     Char(Vec<u8>),
@@ -1045,6 +1825,8 @@ impl IndexData {
     pub(crate) fn as_str(&self) -> Option<&str> {
         match self {
             IndexData::StringTag(s) => Some(s),
+            // the first translation is the untranslated "C" locale
+            IndexData::I18NString(s) => s.first().map(String::as_str),
             _ => None,
         }
     }
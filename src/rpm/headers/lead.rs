@@ -137,7 +137,12 @@ impl Lead {
         Ok(())
     }
 
-    pub(crate) fn new(name: &str) -> Self {
+    /// Whether the lead marks a source package.
+    pub fn is_source_package(&self) -> bool {
+        self.package_type == 1
+    }
+
+    pub(crate) fn new(name: &str, source_package: bool) -> Self {
         let mut name_arr = [0; 66];
         // the last byte needs to be the null terminator
         let name_size = std::cmp::min(name_arr.len() - 1, name.len());
@@ -147,7 +152,7 @@ impl Lead {
             magic: RPM_MAGIC,
             major: 3,
             minor: 0,
-            package_type: 0,
+            package_type: source_package as u16,
             arch: 0,
             name: name_arr,
             os: 1,
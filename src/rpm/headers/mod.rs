@@ -1,9 +1,11 @@
 mod header;
 mod lead;
+mod rich_dependency;
 mod types;
 
 pub use header::*;
 pub use lead::*;
+pub use rich_dependency::*;
 pub use types::*;
 
 #[cfg(feature = "signature-meta")]
@@ -0,0 +1,359 @@
+use super::{operator_sense, Dependency};
+use crate::constants::*;
+use crate::errors::RPMError;
+
+/// A boolean dependency expression as supported by rpm 4.13 and later, i.e. `(foo >= 1.2 or bar)`.
+///
+/// Convert it into a [`Dependency`] to use it with the builder.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RichDependency {
+    /// A plain `name [op version]` dependency.
+    Simple(Dependency),
+    /// All operands must be fulfilled.
+    And(Vec<RichDependency>),
+    /// At least one operand must be fulfilled.
+    Or(Vec<RichDependency>),
+    /// All operands must be fulfilled by the same package.
+    With(Vec<RichDependency>),
+    /// The first operand must be fulfilled by a package not fulfilling the second.
+    Without(Box<RichDependency>, Box<RichDependency>),
+    /// `then` must be fulfilled if `condition` is, `otherwise` if it is not.
+    If {
+        then: Box<RichDependency>,
+        condition: Box<RichDependency>,
+        otherwise: Option<Box<RichDependency>>,
+    },
+    /// `then` must be fulfilled unless `condition` is, `otherwise` if it is.
+    Unless {
+        then: Box<RichDependency>,
+        condition: Box<RichDependency>,
+        otherwise: Option<Box<RichDependency>>,
+    },
+}
+
+impl RichDependency {
+    /// Evaluate the expression against the capabilities of a set of installed packages.
+    ///
+    /// `With` and `Without` can not tell which package a capability belongs to and
+    /// are evaluated like `And` and `And not`.
+    pub fn is_satisfied_by(&self, provides: &[Dependency]) -> bool {
+        match self {
+            RichDependency::Simple(dep) => provides.iter().any(|p| dep.is_satisfied_by(p)),
+            RichDependency::And(operands) | RichDependency::With(operands) => operands
+                .iter()
+                .all(|operand| operand.is_satisfied_by(provides)),
+            RichDependency::Or(operands) => operands
+                .iter()
+                .any(|operand| operand.is_satisfied_by(provides)),
+            RichDependency::Without(dep, excluded) => {
+                dep.is_satisfied_by(provides) && !excluded.is_satisfied_by(provides)
+            }
+            RichDependency::If {
+                then,
+                condition,
+                otherwise,
+            } => {
+                if condition.is_satisfied_by(provides) {
+                    then.is_satisfied_by(provides)
+                } else {
+                    otherwise
+                        .as_ref()
+                        .map_or(true, |otherwise| otherwise.is_satisfied_by(provides))
+                }
+            }
+            RichDependency::Unless {
+                then,
+                condition,
+                otherwise,
+            } => {
+                if condition.is_satisfied_by(provides) {
+                    otherwise
+                        .as_ref()
+                        .map_or(true, |otherwise| otherwise.is_satisfied_by(provides))
+                } else {
+                    then.is_satisfied_by(provides)
+                }
+            }
+        }
+    }
+}
+
+impl From<Dependency> for RichDependency {
+    fn from(dep: Dependency) -> Self {
+        RichDependency::Simple(dep)
+    }
+}
+
+/// Rich dependencies are stored as the dependency name, without a version.
+impl From<RichDependency> for Dependency {
+    fn from(rich: RichDependency) -> Self {
+        match rich {
+            RichDependency::Simple(dep) => dep,
+            rich => Dependency::new(rich.to_string(), RPMSENSE_ANY, "".to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for RichDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |f: &mut std::fmt::Formatter<'_>, operands: &[RichDependency], op: &str| {
+            write!(f, "(")?;
+            for (idx, operand) in operands.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                write!(f, "{}", operand)?;
+            }
+            write!(f, ")")
+        };
+        let conditional = |f: &mut std::fmt::Formatter<'_>,
+                           then: &RichDependency,
+                           op: &str,
+                           condition: &RichDependency,
+                           otherwise: &Option<Box<RichDependency>>| {
+            write!(f, "({} {} {}", then, op, condition)?;
+            if let Some(otherwise) = otherwise {
+                write!(f, " else {}", otherwise)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            RichDependency::Simple(dep) => write!(f, "{}", dep),
+            RichDependency::And(operands) => join(f, operands, "and"),
+            RichDependency::Or(operands) => join(f, operands, "or"),
+            RichDependency::With(operands) => join(f, operands, "with"),
+            RichDependency::Without(dep, excluded) => write!(f, "({} without {})", dep, excluded),
+            RichDependency::If {
+                then,
+                condition,
+                otherwise,
+            } => conditional(f, then, "if", condition, otherwise),
+            RichDependency::Unless {
+                then,
+                condition,
+                otherwise,
+            } => conditional(f, then, "unless", condition, otherwise),
+        }
+    }
+}
+
+impl std::str::FromStr for RichDependency {
+    type Err = RPMError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: raw, pos: 0 };
+        let dep = parser.term()?;
+        parser.skip_whitespace();
+        if parser.pos != raw.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        Ok(dep)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &'static str) -> RPMError {
+        RPMError::InvalidDependency {
+            expression: self.input.to_owned(),
+            reason,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// A name or version, names may contain balanced parentheses like `perl(Foo::Bar)`.
+    fn word(&mut self) -> &'a str {
+        let rest = self.rest();
+        let mut depth = 0usize;
+        let end = rest
+            .char_indices()
+            .find(|(_, c)| match c {
+                '(' => {
+                    depth += 1;
+                    false
+                }
+                ')' if depth > 0 => {
+                    depth -= 1;
+                    false
+                }
+                ')' => true,
+                c => c.is_whitespace(),
+            })
+            .map_or(rest.len(), |(idx, _)| idx);
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn term(&mut self) -> Result<RichDependency, RPMError> {
+        self.skip_whitespace();
+        if self.rest().starts_with('(') {
+            self.expression()
+        } else {
+            self.simple()
+        }
+    }
+
+    fn simple(&mut self) -> Result<RichDependency, RPMError> {
+        let name = self.word();
+        if name.is_empty() {
+            return Err(self.error("expected a dependency name"));
+        }
+        let before_operator = self.pos;
+        self.skip_whitespace();
+        let operator = self.word();
+        let sense = match operator_sense(operator) {
+            Some(sense) => sense,
+            None => {
+                self.pos = before_operator;
+                return Ok(Dependency::any(name).into());
+            }
+        };
+        self.skip_whitespace();
+        let version = self.word();
+        if version.is_empty() {
+            return Err(self.error("expected a version after the operator"));
+        }
+        Ok(Dependency::new(name.to_owned(), sense, version.to_owned()).into())
+    }
+
+    fn expression(&mut self) -> Result<RichDependency, RPMError> {
+        // skip the opening parenthesis
+        self.pos += 1;
+        let first = self.term()?;
+        let mut operators = Vec::new();
+        let mut operands = vec![first];
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with(')') {
+                self.pos += 1;
+                break;
+            }
+            if self.rest().is_empty() {
+                return Err(self.error("missing closing parenthesis"));
+            }
+            let operator = self.word();
+            if !["and", "or", "with", "without", "if", "unless", "else"].contains(&operator) {
+                return Err(self.error("expected a boolean operator"));
+            }
+            operators.push(operator);
+            operands.push(self.term()?);
+        }
+
+        let first_operator = match operators.first() {
+            Some(operator) => *operator,
+            None => return Ok(operands.remove(0)),
+        };
+        match first_operator {
+            "and" | "or" | "with" => {
+                if operators.iter().any(|operator| *operator != first_operator) {
+                    return Err(self.error("mixed operators need parentheses"));
+                }
+                Ok(match first_operator {
+                    "and" => RichDependency::And(operands),
+                    "or" => RichDependency::Or(operands),
+                    _ => RichDependency::With(operands),
+                })
+            }
+            "without" if operators.len() == 1 => {
+                let excluded = operands.pop().unwrap();
+                let dep = operands.pop().unwrap();
+                Ok(RichDependency::Without(Box::new(dep), Box::new(excluded)))
+            }
+            "if" | "unless" if operators.len() == 1 || operators[1..] == ["else"] => {
+                let mut operands = operands.into_iter().map(Box::new);
+                let then = operands.next().unwrap();
+                let condition = operands.next().unwrap();
+                let otherwise = operands.next();
+                Ok(if first_operator == "if" {
+                    RichDependency::If {
+                        then,
+                        condition,
+                        otherwise,
+                    }
+                } else {
+                    RichDependency::Unless {
+                        then,
+                        condition,
+                        otherwise,
+                    }
+                })
+            }
+            _ => Err(self.error("misplaced operator")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() -> Result<(), Box<dyn std::error::Error>> {
+        let dep: RichDependency = "(our-agent-el8 >= 1.2 or our-agent-el9)".parse()?;
+        assert_eq!(
+            RichDependency::Or(vec![
+                Dependency::greater_eq("our-agent-el8", "1.2").into(),
+                Dependency::any("our-agent-el9").into(),
+            ]),
+            dep
+        );
+        assert_eq!("(our-agent-el8 >= 1.2 or our-agent-el9)", dep.to_string());
+
+        for raw in [
+            "(pkgA if pkgB)",
+            "(pkgA if pkgB else pkgC)",
+            "(pkgA unless pkgB else pkgC)",
+            "(perl(Foo::Bar) and (python3dist(baz) < 2 or qux))",
+            "(kernel without kernel-rt)",
+            "(a with b with c)",
+        ] {
+            let dep: RichDependency = raw.parse()?;
+            assert_eq!(raw, dep.to_string());
+        }
+
+        for raw in [
+            "(a and b or c)",
+            "(a",
+            "(a if)",
+            "(a else b)",
+            "(a xor b)",
+            "(a) b",
+        ] {
+            assert!(matches!(
+                raw.parse::<RichDependency>(),
+                Err(RPMError::InvalidDependency { .. })
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate() -> Result<(), Box<dyn std::error::Error>> {
+        let provides = [
+            Dependency::eq("our-agent-el9", "2.0-1"),
+            Dependency::eq("pkgB", "1.0"),
+        ];
+        let satisfied = |raw: &str| -> Result<bool, RPMError> {
+            Ok(raw.parse::<RichDependency>()?.is_satisfied_by(&provides))
+        };
+        assert!(satisfied("(our-agent-el8 or our-agent-el9)")?);
+        assert!(!satisfied("(our-agent-el8 or our-agent-el9 > 2.0)")?);
+        assert!(!satisfied("(pkgA if pkgB)")?);
+        assert!(satisfied("(pkgA if pkgC)")?);
+        assert!(satisfied("(pkgA unless pkgB else our-agent-el9)")?);
+        assert!(satisfied("(pkgB without pkgA)")?);
+        Ok(())
+    }
+}
@@ -4,6 +4,8 @@ use super::*;
 
 use super::IndexEntry;
 use crate::constants::*;
+use crate::signature::algorithm::AlgorithmType;
+use std::convert::TryFrom;
 use std::default::Default;
 
 /// A marker trait for builder stages
@@ -55,16 +57,90 @@ impl<T> SignatureHeaderBuilder<T>
 where
     T: ConstructionStage,
 {
+    /// Record the uncompressed size of the payload archive, including the cpio headers.
+    ///
+    /// Sizes beyond 4 GiB are stored in `RPMSIGTAG_LONGARCHIVESIZE`.
+    pub fn payload_size(mut self, payload_size: u64) -> Self {
+        let entry = match u32::try_from(payload_size) {
+            Ok(size) => IndexEntry::new(
+                IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE,
+                0i32, // externally filled
+                IndexData::Int32(vec![size as i32]),
+            ),
+            Err(_) => IndexEntry::new(
+                IndexSignatureTag::RPMSIGTAG_LONGARCHIVESIZE,
+                0i32, // externally filled
+                IndexData::Int64(vec![payload_size as i64]),
+            ),
+        };
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add the hex encoded IMA signatures of all files, in the order of the files in the header.
+    pub fn file_signatures(mut self, signatures: &[String]) -> Self {
+        let max_len = signatures
+            .iter()
+            .map(|signature| signature.len() / 2)
+            .max()
+            .unwrap_or_default();
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_FILESIGNATURES,
+            0i32, // externally filled
+            IndexData::StringArray(signatures.to_vec()),
+        ));
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_FILESIGNATURE_LENGTH,
+            0i32, // externally filled
+            IndexData::Int32(vec![max_len as i32]),
+        ));
+        self
+    }
+
+    /// Add the base64 encoded fs-verity signatures of all files, in the order of the files
+    /// in the header, made over Merkle trees of SHA-256 digests.
+    pub fn verity_signatures(mut self, signatures: &[String]) -> Self {
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_VERITYSIGNATURES,
+            0i32, // externally filled
+            IndexData::StringArray(signatures.to_vec()),
+        ));
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_VERITYSIGNATUREALGO,
+            0i32, // externally filled
+            IndexData::Int32(vec![FS_VERITY_HASH_ALG_SHA256]),
+        ));
+        self
+    }
+
+    /// Reserve zeroed space of `len` bytes for signatures added later on,
+    /// so they can be written without moving header and payload.
+    pub fn reserved_space(mut self, len: usize) -> Self {
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE,
+            0i32, // externally filled
+            IndexData::Bin(vec![0u8; len]),
+        ));
+        self
+    }
+
     /// Construct the complete signature header.
-    pub fn build(mut self, signature_size: i32) -> Header<IndexSignatureTag> {
-        self.entries.insert(
-            0,
-            IndexEntry::new(
+    ///
+    /// `header_and_content_len` beyond 4 GiB is stored in `RPMSIGTAG_LONGSIGSIZE`.
+    pub fn build(mut self, header_and_content_len: u64) -> Header<IndexSignatureTag> {
+        let entry = match u32::try_from(header_and_content_len) {
+            Ok(size) => IndexEntry::new(
                 IndexSignatureTag::RPMSIGTAG_SIZE,
                 0i32, // externally filled
-                IndexData::Int32(vec![signature_size]),
+                IndexData::Int32(vec![size as i32]),
             ),
-        );
+            Err(_) => IndexEntry::new(
+                IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE,
+                0i32, // externally filled
+                IndexData::Int64(vec![header_and_content_len as i64]),
+            ),
+        };
+        self.entries.insert(0, entry);
 
         Header::<IndexSignatureTag>::from_entries(
             self.entries,
@@ -96,25 +172,79 @@ impl SignatureHeaderBuilder<Empty> {
             phantom: Default::default(),
         }
     }
+
+    /// add a SHA-256 digest over the header only, without the legacy MD5 and SHA-1 digests
+    ///
+    /// Used for header-only signatures, the payload is covered by `RPMTAG_PAYLOADDIGEST`.
+    pub fn add_header_digest(
+        mut self,
+        digest_header_sha256: &str,
+    ) -> SignatureHeaderBuilder<WithDigest> {
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_SHA256,
+            0i32, // filled externally later on
+            IndexData::StringTag(digest_header_sha256.to_string()),
+        ));
+        SignatureHeaderBuilder::<WithDigest> {
+            entries: self.entries,
+            phantom: Default::default(),
+        }
+    }
 }
 
 impl SignatureHeaderBuilder<WithDigest> {
     /// add a signature over the header and a signature accross header and source excluding the static lead
     pub fn add_signature(
-        mut self,
+        self,
         rsa_sig_header_only: &[u8],
         rsa_sig_header_and_archive: &[u8],
     ) -> SignatureHeaderBuilder<WithSignature> {
+        self.add_signature_with_algorithm(
+            AlgorithmType::RSA,
+            rsa_sig_header_only,
+            rsa_sig_header_and_archive,
+        )
+    }
+
+    /// Like [`add_signature`](Self::add_signature), for signatures made with any algorithm.
+    ///
+    /// Like librpm, RSA signatures go into `RPMSIGTAG_RSA` and `RPMSIGTAG_PGP`,
+    /// EdDSA and ECDSA signatures into `RPMSIGTAG_DSA` and `RPMSIGTAG_GPG`.
+    pub fn add_signature_with_algorithm(
+        mut self,
+        algorithm: AlgorithmType,
+        sig_header_only: &[u8],
+        sig_header_and_archive: &[u8],
+    ) -> SignatureHeaderBuilder<WithSignature> {
+        let (header_only_tag, header_and_archive_tag) = signature_tags(algorithm);
         let offset = 0i32; // filled externally later on
         self.entries.push(IndexEntry::new(
-            IndexSignatureTag::RPMSIGTAG_RSA,
+            header_only_tag,
             offset,
-            IndexData::Bin(rsa_sig_header_only.to_vec()),
+            IndexData::Bin(sig_header_only.to_vec()),
         ));
         self.entries.push(IndexEntry::new(
-            IndexSignatureTag::RPMSIGTAG_PGP,
+            header_and_archive_tag,
             offset,
-            IndexData::Bin(rsa_sig_header_and_archive.to_vec()),
+            IndexData::Bin(sig_header_and_archive.to_vec()),
+        ));
+        SignatureHeaderBuilder::<WithSignature> {
+            entries: self.entries,
+            phantom: Default::default(),
+        }
+    }
+
+    /// add a signature over the header only, the payload is covered by the digest within the header
+    pub fn add_header_signature(
+        mut self,
+        algorithm: AlgorithmType,
+        sig_header_only: &[u8],
+    ) -> SignatureHeaderBuilder<WithSignature> {
+        let (header_only_tag, _) = signature_tags(algorithm);
+        self.entries.push(IndexEntry::new(
+            header_only_tag,
+            0i32, // filled externally later on
+            IndexData::Bin(sig_header_only.to_vec()),
         ));
         SignatureHeaderBuilder::<WithSignature> {
             entries: self.entries,
@@ -123,6 +253,20 @@ impl SignatureHeaderBuilder<WithDigest> {
     }
 }
 
+/// The tags of the header only and the header and archive signatures of an algorithm.
+pub(crate) fn signature_tags(algorithm: AlgorithmType) -> (IndexSignatureTag, IndexSignatureTag) {
+    match algorithm {
+        AlgorithmType::RSA => (
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP,
+        ),
+        AlgorithmType::EdDSA | AlgorithmType::ECDSA => (
+            IndexSignatureTag::RPMSIGTAG_DSA,
+            IndexSignatureTag::RPMSIGTAG_GPG,
+        ),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -138,7 +282,7 @@ mod test {
         let header = builder
             .add_digest("", &digest_header_and_archive[..])
             .add_signature(&rsa_sig_header_only[..], &rsa_sig_header_and_archive[..])
-            .build(32i32);
+            .build(32);
 
         assert!(header
             .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_RSA)
@@ -153,4 +297,48 @@ mod test {
             .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SHA1)
             .is_ok());
     }
+
+    #[test]
+    fn signature_builder_header_only() {
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .add_header_digest("abcd")
+            .add_header_signature(AlgorithmType::EdDSA, &[0u8; 32][..])
+            .build(32);
+
+        for tag in &[
+            IndexSignatureTag::RPMSIGTAG_SHA256,
+            IndexSignatureTag::RPMSIGTAG_DSA,
+        ] {
+            assert!(header.find_entry_or_err(tag).is_ok());
+        }
+        for tag in &[
+            IndexSignatureTag::RPMSIGTAG_MD5,
+            IndexSignatureTag::RPMSIGTAG_SHA1,
+            IndexSignatureTag::RPMSIGTAG_GPG,
+            IndexSignatureTag::RPMSIGTAG_RSA,
+        ] {
+            assert!(header.find_entry_or_err(tag).is_err());
+        }
+    }
+
+    #[test]
+    fn signature_builder_large_sizes() {
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .payload_size(5 << 30)
+            .build(6 << 30);
+        assert_eq!(6 << 30, header.get_size().unwrap());
+        assert_eq!(5 << 30, header.get_payload_size().unwrap());
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SIZE)
+            .is_err());
+
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .payload_size(u32::MAX as u64)
+            .build(32);
+        assert_eq!(32, header.get_size().unwrap());
+        assert_eq!(u32::MAX as u64, header.get_payload_size().unwrap());
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE)
+            .is_err());
+    }
 }
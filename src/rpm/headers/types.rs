@@ -1,12 +1,14 @@
 //! A collection of types used in various header records.
+use super::RichDependency;
 use crate::{constants::*, errors};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 /// Describes a file present in the rpm file.
 pub struct RPMFileEntry {
-    pub(crate) size: i32,
+    pub(crate) size: u64,
     pub(crate) mode: FileMode,
     pub(crate) modified_at: i32,
-    pub(crate) sha_checksum: String,
     pub(crate) link: String,
     pub(crate) flag: i32,
     pub(crate) user: String,
@@ -14,6 +16,9 @@ pub struct RPMFileEntry {
     pub(crate) base_name: String,
     pub(crate) dir: String,
     pub(crate) content: Option<Vec<u8>>,
+    pub(crate) selinux_context: Option<String>,
+    pub(crate) xattrs: BTreeMap<String, Vec<u8>>,
+    pub(crate) caps: Option<String>,
 }
 
 #[non_exhaustive]
@@ -169,7 +174,7 @@ impl From<FileMode> for i16 {
 
 impl From<FileMode> for u16 {
     fn from(mode: FileMode) -> Self {
-        mode.raw_mode() as u16
+        mode.raw_mode()
     }
 }
 
@@ -184,6 +189,9 @@ pub struct RPMFileOptions {
     pub(crate) mode: FileMode,
     pub(crate) flag: i32,
     pub(crate) inherit_permissions: bool,
+    pub(crate) selinux_context: Option<String>,
+    pub(crate) xattrs: BTreeMap<String, Vec<u8>>,
+    pub(crate) caps: Option<String>,
 }
 
 impl RPMFileOptions {
@@ -198,6 +206,9 @@ impl RPMFileOptions {
                 mode: FileMode::regular(0o664),
                 flag: 0,
                 inherit_permissions: true,
+                selinux_context: None,
+                xattrs: BTreeMap::new(),
+                caps: None,
             },
         }
     }
@@ -238,6 +249,35 @@ impl RPMFileOptionsBuilder {
         self.inner.flag = RPMFILE_CONFIG;
         self
     }
+
+    /// Label the file with a SELinux context, i.e. `system_u:object_r:bin_t:s0`.
+    pub fn selinux_context<T: Into<String>>(mut self, context: T) -> Self {
+        self.inner.selinux_context = Some(context.into());
+        self
+    }
+
+    /// Grant POSIX file capabilities in the text form of `cap_from_text(3)`,
+    /// i.e. `cap_net_raw=ep`, which rpm applies on installation.
+    pub fn caps<T: Into<String>>(mut self, caps: T) -> Self {
+        self.inner.caps = Some(caps.into());
+        self
+    }
+
+    /// Add an extended attribute, i.e. `user.checksum`.
+    ///
+    /// Setting the same name twice overrides the previous value.
+    ///
+    /// The attributes are stored in `RPMTAG_XATTRSDICT` in a fez-only format,
+    /// rpm itself neither reads nor applies them on installation. Use
+    /// [`selinux_context`](Self::selinux_context) for SELinux labels.
+    pub fn xattr<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        self.inner.xattrs.insert(name.into(), value.into());
+        self
+    }
 }
 
 impl From<RPMFileOptionsBuilder> for RPMFileOptions {
@@ -246,7 +286,35 @@ impl From<RPMFileOptionsBuilder> for RPMFileOptions {
     }
 }
 
+/// Encode the extended attributes of a single file as one `RPMTAG_XATTRSDICT` entry.
+///
+/// rpm itself does not define the format of the dictionary, so we use one
+/// `name=hex(value)` pair per line. Names are sorted, hence equal sets of
+/// attributes always yield the same entry. An empty set is the empty string.
+pub(crate) fn encode_xattrs(xattrs: &BTreeMap<String, Vec<u8>>) -> String {
+    xattrs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, hex::encode(value)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Inverse of [`encode_xattrs`].
+pub(crate) fn decode_xattrs(entry: &str) -> Result<BTreeMap<String, Vec<u8>>, errors::RPMError> {
+    entry
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| errors::RPMError::InvalidXattrEntry(line.to_owned()))?;
+            Ok((name.to_owned(), hex::decode(value)?))
+        })
+        .collect()
+}
+
 /// Description of a dependency as present in a RPM header record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Dependency {
     pub(crate) dep_name: String,
     pub(crate) sense: u32,
@@ -309,17 +377,786 @@ impl Dependency {
         Self::new(dep_name.into(), RPMSENSE_ANY, "".to_string())
     }
 
-    fn new(dep_name: String, sense: u32, version: String) -> Self {
+    /// A requirement on a feature of rpm itself, i.e. `rpmlib(PayloadIsZstd) <= 5.4.18-1`.
+    pub(crate) fn rpm_lib<E, T>(feature: T, version: E) -> Self
+    where
+        T: AsRef<str>,
+        E: Into<String>,
+    {
+        Self::new(
+            format!("rpmlib({})", feature.as_ref()),
+            RPMSENSE_RPMLIB | RPMSENSE_LESS | RPMSENSE_EQUAL,
+            version.into(),
+        )
+    }
+
+    pub(crate) fn new(dep_name: String, sense: u32, version: String) -> Self {
         Dependency {
             dep_name,
             sense,
             version,
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.dep_name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// A combination of the `RPMSENSE_*` constants.
+    pub fn sense(&self) -> u32 {
+        self.sense
+    }
+
+    /// Add a qualifier, i.e. `Requires(pre)`.
+    pub fn qualifier(mut self, qualifier: DependencyQualifier) -> Self {
+        self.sense |= qualifier.sense();
+        self
+    }
+
+    /// Add a comma separated list of qualifiers as found in `Requires(pre,postun)`.
+    pub fn qualifiers(self, raw: &str) -> Result<Self, errors::RPMError> {
+        raw.split(',')
+            .try_fold(self, |dep, qualifier| Ok(dep.qualifier(qualifier.parse()?)))
+    }
+
+    /// Check whether `provide` satisfies this dependency, i.e. whether their version ranges overlap.
+    pub fn is_satisfied_by(&self, provide: &Dependency) -> bool {
+        if self.dep_name != provide.dep_name {
+            return false;
+        }
+        let (ours, theirs) = (
+            self.sense & RPMSENSE_SENSEMASK,
+            provide.sense & RPMSENSE_SENSEMASK,
+        );
+        if ours == 0 || theirs == 0 || self.version.is_empty() || provide.version.is_empty() {
+            return true;
+        }
+        match compare_dependency_evr(&provide.version, &self.version) {
+            Ordering::Less => theirs & RPMSENSE_GREATER != 0 || ours & RPMSENSE_LESS != 0,
+            Ordering::Greater => theirs & RPMSENSE_LESS != 0 || ours & RPMSENSE_GREATER != 0,
+            Ordering::Equal => {
+                (theirs & RPMSENSE_EQUAL != 0 && ours & RPMSENSE_EQUAL != 0)
+                    || (theirs & RPMSENSE_LESS != 0 && ours & RPMSENSE_LESS != 0)
+                    || (theirs & RPMSENSE_GREATER != 0 && ours & RPMSENSE_GREATER != 0)
+            }
+        }
+    }
+}
+
+/// Formats as `name [op [epoch:]version[-release]]`, qualifiers are not part of the output.
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.dep_name)?;
+        match operator_symbol(self.sense) {
+            Some(op) if !self.version.is_empty() => write!(f, " {} {}", op, self.version),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parses `name`, `name op [epoch:]version[-release]` and rich dependencies like `(foo or bar)`.
+impl std::str::FromStr for Dependency {
+    type Err = errors::RPMError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let trimmed = raw.trim();
+        if trimmed.starts_with('(') {
+            return Ok(trimmed.parse::<RichDependency>()?.into());
+        }
+        let error = |reason| errors::RPMError::InvalidDependency {
+            expression: raw.to_owned(),
+            reason,
+        };
+        let (name, rest) = match trimmed.find(['<', '>', '=']) {
+            Some(idx) => trimmed.split_at(idx),
+            None => (trimmed, ""),
+        };
+        let name = name.trim_end();
+        if name.is_empty() {
+            return Err(error("expected a dependency name"));
+        }
+        if name.contains(char::is_whitespace) {
+            return Err(error("expected an operator after the name"));
+        }
+        if rest.is_empty() {
+            return Ok(Dependency::any(name));
+        }
+        let op_len = rest
+            .find(|c| !matches!(c, '<' | '>' | '='))
+            .unwrap_or(rest.len());
+        let sense = operator_sense(&rest[..op_len]).ok_or_else(|| error("unknown operator"))?;
+        let version = rest[op_len..].trim();
+        if version.is_empty() {
+            return Err(error("expected a version after the operator"));
+        }
+        if version.contains(char::is_whitespace) {
+            return Err(error("unexpected trailing input"));
+        }
+        if let Some((epoch, _)) = version.split_once(':') {
+            if epoch.is_empty() || !epoch.chars().all(|c| c.is_ascii_digit()) {
+                return Err(error("the epoch must be numeric"));
+            }
+        }
+        Ok(Dependency::new(name.to_owned(), sense, version.to_owned()))
+    }
+}
+
+/// When a requirement has to be present, as in `Requires(pre,postun): foo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DependencyQualifier {
+    Pre,
+    Post,
+    PreUn,
+    PostUn,
+    PreTrans,
+    PostTrans,
+    Verify,
+    /// The dependency is the interpreter of a scriptlet.
+    Interp,
+    /// The legacy `PreReq: foo`, which is the same as `Requires(prereq): foo`.
+    PreReq,
+}
+
+impl DependencyQualifier {
+    pub fn sense(&self) -> u32 {
+        match self {
+            DependencyQualifier::Pre => RPMSENSE_SCRIPT_PRE,
+            DependencyQualifier::Post => RPMSENSE_SCRIPT_POST,
+            DependencyQualifier::PreUn => RPMSENSE_SCRIPT_PREUN,
+            DependencyQualifier::PostUn => RPMSENSE_SCRIPT_POSTUN,
+            DependencyQualifier::PreTrans => RPMSENSE_PRETRANS,
+            DependencyQualifier::PostTrans => RPMSENSE_POSTTRANS,
+            DependencyQualifier::Verify => RPMSENSE_SCRIPT_VERIFY,
+            DependencyQualifier::Interp => RPMSENSE_INTERP,
+            DependencyQualifier::PreReq => RPMSENSE_PREREQ,
+        }
+    }
+}
+
+impl std::str::FromStr for DependencyQualifier {
+    type Err = errors::RPMError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim() {
+            "pre" => Ok(DependencyQualifier::Pre),
+            "post" => Ok(DependencyQualifier::Post),
+            "preun" => Ok(DependencyQualifier::PreUn),
+            "postun" => Ok(DependencyQualifier::PostUn),
+            "pretrans" => Ok(DependencyQualifier::PreTrans),
+            "posttrans" => Ok(DependencyQualifier::PostTrans),
+            "verify" => Ok(DependencyQualifier::Verify),
+            "interp" => Ok(DependencyQualifier::Interp),
+            "prereq" => Ok(DependencyQualifier::PreReq),
+            _ => Err(errors::RPMError::InvalidDependency {
+                expression: raw.to_owned(),
+                reason: "unknown qualifier",
+            }),
+        }
+    }
+}
+
+pub(crate) fn operator_symbol(sense: u32) -> Option<&'static str> {
+    match sense & (RPMSENSE_LESS | RPMSENSE_GREATER | RPMSENSE_EQUAL) {
+        s if s == RPMSENSE_LESS => Some("<"),
+        s if s == RPMSENSE_LESS | RPMSENSE_EQUAL => Some("<="),
+        s if s == RPMSENSE_EQUAL => Some("="),
+        s if s == RPMSENSE_GREATER | RPMSENSE_EQUAL => Some(">="),
+        s if s == RPMSENSE_GREATER => Some(">"),
+        _ => None,
+    }
+}
+
+pub(crate) fn operator_sense(symbol: &str) -> Option<u32> {
+    match symbol {
+        "<" => Some(RPMSENSE_LESS),
+        "<=" | "=<" => Some(RPMSENSE_LESS | RPMSENSE_EQUAL),
+        "=" | "==" => Some(RPMSENSE_EQUAL),
+        ">=" | "=>" => Some(RPMSENSE_GREATER | RPMSENSE_EQUAL),
+        ">" => Some(RPMSENSE_GREATER),
+        _ => None,
+    }
+}
+
+/// The comparison bits of a dependency sense.
+const RPMSENSE_SENSEMASK: u32 = RPMSENSE_LESS | RPMSENSE_GREATER | RPMSENSE_EQUAL;
+
+/// Split `[epoch:]version[-release]`, a missing epoch counts as 0.
+fn split_evr(evr: &str) -> (u32, &str, Option<&str>) {
+    let (epoch, version_release) = match evr.split_once(':') {
+        Some((epoch, rest)) if epoch.chars().all(|c| c.is_ascii_digit()) => {
+            (epoch.parse().unwrap_or(0), rest)
+        }
+        _ => (0, evr),
+    };
+    match version_release.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, version_release, None),
+    }
+}
+
+/// Compare two `[epoch:]version[-release]` strings the way rpm orders package versions.
+pub fn compare_evr(a: &str, b: &str) -> Ordering {
+    let (a_epoch, a_version, a_release) = split_evr(a);
+    let (b_epoch, b_version, b_release) = split_evr(b);
+    a_epoch
+        .cmp(&b_epoch)
+        .then_with(|| compare_versions(a_version, b_version))
+        .then_with(|| compare_versions(a_release.unwrap_or(""), b_release.unwrap_or("")))
+}
+
+/// Like [`compare_evr`], but a missing release matches any release as dependencies do.
+fn compare_dependency_evr(a: &str, b: &str) -> Ordering {
+    let (a_epoch, a_version, a_release) = split_evr(a);
+    let (b_epoch, b_version, b_release) = split_evr(b);
+    let release = match (a_release, b_release) {
+        (Some(a_release), Some(b_release)) => compare_versions(a_release, b_release),
+        _ => Ordering::Equal,
+    };
+    a_epoch
+        .cmp(&b_epoch)
+        .then_with(|| compare_versions(a_version, b_version))
+        .then(release)
+}
+
+/// Split off the leading run of digits or letters.
+fn split_segment(s: &[u8], numeric: bool) -> (&[u8], &[u8]) {
+    let end = s
+        .iter()
+        .position(|c| {
+            if numeric {
+                !c.is_ascii_digit()
+            } else {
+                !c.is_ascii_alphabetic()
+            }
+        })
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn trim_zeros(s: &[u8]) -> &[u8] {
+    let start = s.iter().position(|c| *c != b'0').unwrap_or(s.len());
+    &s[start..]
+}
+
+/// Compare two version or release strings like `rpmvercmp`.
+///
+/// `~` sorts before anything, even the end of the string, `^` after the end of the string
+/// but before anything else.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let is_separator = |c: &u8| !c.is_ascii_alphanumeric() && *c != b'~' && *c != b'^';
+    let (mut one, mut two) = (a.as_bytes(), b.as_bytes());
+    loop {
+        while one.first().map_or(false, is_separator) {
+            one = &one[1..];
+        }
+        while two.first().map_or(false, is_separator) {
+            two = &two[1..];
+        }
+
+        if one.first() == Some(&b'~') || two.first() == Some(&b'~') {
+            if one.first() != Some(&b'~') {
+                return Ordering::Greater;
+            }
+            if two.first() != Some(&b'~') {
+                return Ordering::Less;
+            }
+            one = &one[1..];
+            two = &two[1..];
+            continue;
+        }
+
+        if one.first() == Some(&b'^') || two.first() == Some(&b'^') {
+            if one.is_empty() {
+                return Ordering::Less;
+            }
+            if two.is_empty() {
+                return Ordering::Greater;
+            }
+            if one[0] != b'^' {
+                return Ordering::Greater;
+            }
+            if two[0] != b'^' {
+                return Ordering::Less;
+            }
+            one = &one[1..];
+            two = &two[1..];
+            continue;
+        }
+
+        if one.is_empty() || two.is_empty() {
+            break;
+        }
+
+        let numeric = one[0].is_ascii_digit();
+        let (segment_one, rest_one) = split_segment(one, numeric);
+        let (segment_two, rest_two) = split_segment(two, numeric);
+        // numeric segments are newer than alphabetic ones
+        if segment_two.is_empty() {
+            return if numeric {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let ordering = if numeric {
+            let (segment_one, segment_two) = (trim_zeros(segment_one), trim_zeros(segment_two));
+            segment_one
+                .len()
+                .cmp(&segment_two.len())
+                .then_with(|| segment_one.cmp(segment_two))
+        } else {
+            segment_one.cmp(segment_two)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        one = rest_one;
+        two = rest_two;
+    }
+    match (one.is_empty(), two.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, _) => Ordering::Greater,
+    }
+}
+
+/// The event in the lifecycle of another package a [`Trigger`] reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriggerType {
+    /// `%triggerprein`, before the other package is installed.
+    PreInstall,
+    /// `%triggerin`, after the other package is installed.
+    Install,
+    /// `%triggerun`, before the other package is removed.
+    Uninstall,
+    /// `%triggerpostun`, after the other package is removed.
+    PostUninstall,
+}
+
+impl TriggerType {
+    /// The `RPMSENSE_TRIGGER*` flag of this trigger type.
+    pub fn sense(&self) -> u32 {
+        match self {
+            Self::PreInstall => RPMSENSE_TRIGGERPREIN,
+            Self::Install => RPMSENSE_TRIGGERIN,
+            Self::Uninstall => RPMSENSE_TRIGGERUN,
+            Self::PostUninstall => RPMSENSE_TRIGGERPOSTUN,
+        }
+    }
+
+    pub(crate) fn from_sense(sense: u32) -> Option<Self> {
+        [
+            Self::PreInstall,
+            Self::Install,
+            Self::Uninstall,
+            Self::PostUninstall,
+        ]
+        .iter()
+        .copied()
+        .find(|ty| sense & ty.sense() != 0)
+    }
+}
+
+/// A script run when packages matching any of its conditions are installed or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Trigger {
+    pub ty: TriggerType,
+    /// The packages this trigger reacts to, i.e. `Dependency::any("plugin")`.
+    pub conditions: Vec<Dependency>,
+    /// Triggers do not support interpreter arguments.
+    pub scriptlet: Scriptlet,
+}
+
+impl Trigger {
+    pub fn new<T: Into<Scriptlet>>(ty: TriggerType, scriptlet: T) -> Self {
+        Trigger {
+            ty,
+            conditions: Vec::new(),
+            scriptlet: scriptlet.into(),
+        }
+    }
+
+    pub fn condition(mut self, dep: Dependency) -> Self {
+        self.conditions.push(dep);
+        self
+    }
+}
+
+/// How often a [`FileTrigger`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileTriggerScope {
+    /// `%filetrigger*`, once for each package touching matching files.
+    Package,
+    /// `%transfiletrigger*`, once per transaction.
+    Transaction,
+}
+
+/// The priority rpm assigns to file triggers without an explicit one.
+pub const DEFAULT_FILE_TRIGGER_PRIORITY: u32 = 1_000_000;
+
+/// A script run when files below any of its path prefixes are installed or removed.
+///
+/// The matching paths are passed to the script on stdin.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileTrigger {
+    /// File triggers do not support [`TriggerType::PreInstall`].
+    pub ty: TriggerType,
+    pub scope: FileTriggerScope,
+    /// Path prefixes, i.e. `/usr/lib/ourapp/plugins/`.
+    pub prefixes: Vec<String>,
+    /// Triggers with a higher priority run first.
+    pub priority: u32,
+    /// Triggers do not support interpreter arguments.
+    pub scriptlet: Scriptlet,
+}
+
+impl FileTrigger {
+    pub fn new<T: Into<Scriptlet>>(ty: TriggerType, scope: FileTriggerScope, scriptlet: T) -> Self {
+        FileTrigger {
+            ty,
+            scope,
+            prefixes: Vec::new(),
+            priority: DEFAULT_FILE_TRIGGER_PRIORITY,
+            scriptlet: scriptlet.into(),
+        }
+    }
+
+    pub fn prefix<T: Into<String>>(mut self, prefix: T) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// The header tags of one kind of trigger.
+pub(crate) struct TriggerIndexTags {
+    pub(crate) scripts: IndexTag,
+    pub(crate) progs: IndexTag,
+    pub(crate) script_flags: IndexTag,
+    pub(crate) names: IndexTag,
+    pub(crate) versions: IndexTag,
+    pub(crate) flags: IndexTag,
+    pub(crate) indexes: IndexTag,
+    /// Only file triggers have priorities.
+    pub(crate) priorities: Option<IndexTag>,
+}
+
+impl TriggerIndexTags {
+    pub(crate) const PACKAGE: TriggerIndexTags = TriggerIndexTags {
+        scripts: IndexTag::RPMTAG_TRIGGERSCRIPTS,
+        progs: IndexTag::RPMTAG_TRIGGERSCRIPTPROG,
+        script_flags: IndexTag::RPMTAG_TRIGGERSCRIPTFLAGS,
+        names: IndexTag::RPMTAG_TRIGGERNAME,
+        versions: IndexTag::RPMTAG_TRIGGERVERSION,
+        flags: IndexTag::RPMTAG_TRIGGERFLAGS,
+        indexes: IndexTag::RPMTAG_TRIGGERINDEX,
+        priorities: None,
+    };
+
+    pub(crate) const FILE: TriggerIndexTags = TriggerIndexTags {
+        scripts: IndexTag::RPMTAG_FILETRIGGERSCRIPTS,
+        progs: IndexTag::RPMTAG_FILETRIGGERSCRIPTPROG,
+        script_flags: IndexTag::RPMTAG_FILETRIGGERSCRIPTFLAGS,
+        names: IndexTag::RPMTAG_FILETRIGGERNAME,
+        versions: IndexTag::RPMTAG_FILETRIGGERVERSION,
+        flags: IndexTag::RPMTAG_FILETRIGGERFLAGS,
+        indexes: IndexTag::RPMTAG_FILETRIGGERINDEX,
+        priorities: Some(IndexTag::RPMTAG_FILETRIGGERPRIORITIES),
+    };
+
+    pub(crate) const TRANSACTION_FILE: TriggerIndexTags = TriggerIndexTags {
+        scripts: IndexTag::RPMTAG_TRANSFILETRIGGERSCRIPTS,
+        progs: IndexTag::RPMTAG_TRANSFILETRIGGERSCRIPTPROG,
+        script_flags: IndexTag::RPMTAG_TRANSFILETRIGGERSCRIPTFLAGS,
+        names: IndexTag::RPMTAG_TRANSFILETRIGGERNAME,
+        versions: IndexTag::RPMTAG_TRANSFILETRIGGERVERSION,
+        flags: IndexTag::RPMTAG_TRANSFILETRIGGERFLAGS,
+        indexes: IndexTag::RPMTAG_TRANSFILETRIGGERINDEX,
+        priorities: Some(IndexTag::RPMTAG_TRANSFILETRIGGERPRIORITIES),
+    };
+
+    pub(crate) fn for_scope(scope: FileTriggerScope) -> &'static TriggerIndexTags {
+        match scope {
+            FileTriggerScope::Package => &Self::FILE,
+            FileTriggerScope::Transaction => &Self::TRANSACTION_FILE,
+        }
+    }
+}
+
+/// The point in the package lifecycle at which a [`Scriptlet`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScriptletType {
+    /// `%pretrans`, before the transaction starts.
+    PreTransaction,
+    /// `%pre`, before the package is installed.
+    PreInstall,
+    /// `%post`, after the package is installed.
+    PostInstall,
+    /// `%preun`, before the package is removed.
+    PreUninstall,
+    /// `%postun`, after the package is removed.
+    PostUninstall,
+    /// `%posttrans`, after the transaction completed.
+    PostTransaction,
+    /// `%verifyscript`, run by `rpm --verify`.
+    Verify,
+}
+
+/// The header tags holding the script body, the interpreter and the flags of one scriptlet type.
+pub(crate) struct ScriptletIndexTags {
+    pub(crate) script: IndexTag,
+    pub(crate) program: IndexTag,
+    pub(crate) flags: IndexTag,
+}
+
+impl ScriptletType {
+    /// All scriptlet types, in the order they run during a transaction.
+    pub const ALL: [ScriptletType; 7] = [
+        Self::PreTransaction,
+        Self::PreInstall,
+        Self::PostInstall,
+        Self::PreUninstall,
+        Self::PostUninstall,
+        Self::PostTransaction,
+        Self::Verify,
+    ];
+
+    pub(crate) fn index_tags(&self) -> ScriptletIndexTags {
+        let (script, program, flags) = match self {
+            Self::PreTransaction => (
+                IndexTag::RPMTAG_PRETRANS,
+                IndexTag::RPMTAG_PRETRANSPROG,
+                IndexTag::RPMTAG_PRETRANSFLAGS,
+            ),
+            Self::PreInstall => (
+                IndexTag::RPMTAG_PREIN,
+                IndexTag::RPMTAG_PREINPROG,
+                IndexTag::RPMTAG_PREINFLAGS,
+            ),
+            Self::PostInstall => (
+                IndexTag::RPMTAG_POSTIN,
+                IndexTag::RPMTAG_POSTINPROG,
+                IndexTag::RPMTAG_POSTINFLAGS,
+            ),
+            Self::PreUninstall => (
+                IndexTag::RPMTAG_PREUN,
+                IndexTag::RPMTAG_PREUNPROG,
+                IndexTag::RPMTAG_PREUNFLAGS,
+            ),
+            Self::PostUninstall => (
+                IndexTag::RPMTAG_POSTUN,
+                IndexTag::RPMTAG_POSTUNPROG,
+                IndexTag::RPMTAG_POSTUNFLAGS,
+            ),
+            Self::PostTransaction => (
+                IndexTag::RPMTAG_POSTTRANS,
+                IndexTag::RPMTAG_POSTTRANSPROG,
+                IndexTag::RPMTAG_POSTTRANSFLAGS,
+            ),
+            Self::Verify => (
+                IndexTag::RPMTAG_VERIFYSCRIPT,
+                IndexTag::RPMTAG_VERIFYSCRIPTPROG,
+                IndexTag::RPMTAG_VERIFYSCRIPTFLAGS,
+            ),
+        };
+        ScriptletIndexTags {
+            script,
+            program,
+            flags,
+        }
+    }
+
+    /// The dependency sense of the `Requires(...)` on the interpreter.
+    pub(crate) fn interpreter_sense(&self) -> u32 {
+        RPMSENSE_INTERP
+            | match self {
+                Self::PreTransaction => RPMSENSE_PRETRANS,
+                Self::PreInstall => RPMSENSE_SCRIPT_PRE,
+                Self::PostInstall => RPMSENSE_SCRIPT_POST,
+                Self::PreUninstall => RPMSENSE_SCRIPT_PREUN,
+                Self::PostUninstall => RPMSENSE_SCRIPT_POSTUN,
+                Self::PostTransaction => RPMSENSE_POSTTRANS,
+                Self::Verify => RPMSENSE_SCRIPT_VERIFY,
+            }
+    }
+}
+
+/// The interpreter rpm uses if a scriptlet does not specify one.
+pub const DEFAULT_SCRIPTLET_INTERPRETER: &str = "/bin/sh";
+
+/// The pseudo interpreter to run a scriptlet with rpm's embedded lua.
+pub const LUA_SCRIPTLET_INTERPRETER: &str = "<lua>";
+
+/// A script together with the interpreter running it.
+///
+/// Plain strings convert into a scriptlet run by `/bin/sh`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Scriptlet {
+    /// The script body.
+    pub script: String,
+    /// The interpreter and its arguments, i.e. `["/bin/bash", "-e"]`.
+    pub program: Vec<String>,
+    /// A combination of the `RPMSCRIPT_FLAG_*` constants.
+    pub flags: u32,
+}
+
+impl Scriptlet {
+    pub fn new<T: Into<String>>(script: T) -> Self {
+        Scriptlet {
+            script: script.into(),
+            program: vec![DEFAULT_SCRIPTLET_INTERPRETER.to_string()],
+            flags: 0,
+        }
+    }
+
+    /// Run the script with a different interpreter, i.e. `vec!["/usr/bin/python3"]` or `vec!["<lua>"]`.
+    pub fn prog<T: Into<String>>(mut self, program: Vec<T>) -> Self {
+        self.program = program.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Whether the script runs in rpm's embedded lua interpreter.
+    pub fn is_lua(&self) -> bool {
+        self.program
+            .first()
+            .map_or(false, |prog| prog == LUA_SCRIPTLET_INTERPRETER)
+    }
+}
+
+impl From<&str> for Scriptlet {
+    fn from(script: &str) -> Self {
+        Self::new(script)
+    }
+}
+
+impl From<String> for Scriptlet {
+    fn from(script: String) -> Self {
+        Self::new(script)
+    }
 }
 
 mod test {
 
+    #[test]
+    fn test_xattrs_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        use super::*;
+
+        let mut xattrs = BTreeMap::new();
+        assert_eq!("", encode_xattrs(&xattrs));
+        assert!(decode_xattrs("")?.is_empty());
+
+        xattrs.insert("user.foo".to_string(), b"bar".to_vec());
+        xattrs.insert("security.ima".to_string(), vec![0x03, 0x02, 0x00]);
+        let encoded = encode_xattrs(&xattrs);
+        assert_eq!("security.ima=030200\nuser.foo=626172", encoded);
+        assert_eq!(xattrs, decode_xattrs(&encoded)?);
+
+        assert!(decode_xattrs("user.foo").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use super::*;
+        use std::cmp::Ordering::*;
+
+        for (a, b, expected) in [
+            ("1.0", "1.0", Equal),
+            ("1.0", "1.0.1", Less),
+            ("1.10", "1.9", Greater),
+            ("1.010", "1.10", Equal),
+            ("2.0a", "2.0", Greater),
+            ("1a", "11", Less),
+            ("1.0~rc1", "1.0", Less),
+            ("1.0~rc1", "1.0~rc2", Less),
+            ("1.0^git1", "1.0", Greater),
+            ("1.0^git1", "1.0.1", Less),
+            ("1_0", "1.0", Equal),
+        ] {
+            assert_eq!(expected, compare_versions(a, b), "{} vs {}", a, b);
+        }
+        assert_eq!(Greater, compare_evr("1:1.0-1", "2.0-1"));
+        assert_eq!(Less, compare_evr("1.0-1", "1.0-2"));
+    }
+
+    #[test]
+    fn test_dependency_parse_and_format() -> Result<(), Box<dyn std::error::Error>> {
+        use super::*;
+
+        for (raw, expected) in [
+            ("foo", Dependency::any("foo")),
+            ("foo >= 1:2.0-3", Dependency::greater_eq("foo", "1:2.0-3")),
+            ("foo<=2", Dependency::less_eq("foo", "2")),
+            ("foo == 2", Dependency::eq("foo", "2")),
+            (
+                "perl(Foo::Bar) > 1.0",
+                Dependency::greater("perl(Foo::Bar)", "1.0"),
+            ),
+            (" foo < 3 ", Dependency::less("foo", "3")),
+        ] {
+            assert_eq!(expected, raw.parse::<Dependency>()?, "{}", raw);
+        }
+        assert_eq!(
+            "foo >= 1:2.0-3",
+            Dependency::greater_eq("foo", "1:2.0-3").to_string()
+        );
+        assert_eq!("foo", Dependency::any("foo").to_string());
+        assert_eq!(
+            "(foo or bar)",
+            "(foo or bar)".parse::<Dependency>()?.to_string()
+        );
+
+        for raw in [
+            "",
+            ">= 1",
+            "foo >=",
+            "foo bar",
+            "foo => 1 2",
+            "foo >< 1",
+            "foo = x:1",
+        ] {
+            assert!(raw.parse::<Dependency>().is_err(), "{}", raw);
+        }
+
+        let dep = Dependency::any("/usr/bin/python3").qualifiers("pre,interp")?;
+        assert_eq!(RPMSENSE_SCRIPT_PRE | RPMSENSE_INTERP, dep.sense());
+        assert!(Dependency::any("foo").qualifiers("pre,bogus").is_err());
+        let dep = Dependency::any("foo").qualifiers("prereq")?;
+        assert_eq!(RPMSENSE_PREREQ, dep.sense());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_is_satisfied_by() {
+        use super::*;
+
+        let provide = Dependency::eq("foo", "1.2-3");
+        assert!(Dependency::any("foo").is_satisfied_by(&provide));
+        assert!(Dependency::greater_eq("foo", "1.2").is_satisfied_by(&provide));
+        assert!(Dependency::eq("foo", "1.2").is_satisfied_by(&provide));
+        assert!(!Dependency::greater("foo", "1.2").is_satisfied_by(&provide));
+        assert!(!Dependency::less("foo", "1.2-3").is_satisfied_by(&provide));
+        assert!(!Dependency::any("bar").is_satisfied_by(&provide));
+        assert!(Dependency::less("foo", "2").is_satisfied_by(&Dependency::any("foo")));
+    }
+
     #[test]
     fn test_file_mode() -> Result<(), Box<dyn std::error::Error>> {
         use super::*;
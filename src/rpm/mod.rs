@@ -1,7 +1,12 @@
 mod builder;
 mod compressor;
+#[cfg(feature = "dependency-generator")]
+mod dependency_generator;
+mod file_class;
 mod headers;
 mod package;
+#[cfg(feature = "signature-meta")]
+mod package_file;
 
 #[cfg(feature = "signature-meta")]
 pub mod signature;
@@ -11,5 +16,7 @@ pub use headers::*;
 pub use compressor::*;
 
 pub use package::*;
+#[cfg(feature = "signature-meta")]
+pub use package_file::*;
 
 pub use builder::*;
@@ -9,14 +9,14 @@ use crate::errors::*;
 
 use super::Lead;
 
+#[cfg(feature = "signature-verity")]
+use super::builder::{cpio_padding, CPIO_STRIPPED_MAGIC};
 #[cfg(feature = "signature-meta")]
 use crate::sequential_cursor::SeqCursor;
 #[cfg(feature = "signature-meta")]
 use crate::signature;
 
 use std::io::Read;
-#[cfg(feature = "signature-meta")]
-use std::io::{Seek, SeekFrom};
 
 /// A complete rpm file.
 ///
@@ -64,89 +64,236 @@ impl RPMPackage {
         Ok(())
     }
 
-    // TODO allow passing an external signer/verifier
-
     /// sign all headers (except for the lead) using an external key and store it as the initial header
     #[cfg(feature = "signature-meta")]
     pub fn sign<S>(&mut self, signer: S) -> Result<(), RPMError>
     where
-        S: signature::Signing<signature::algorithm::RSA, Signature = Vec<u8>>,
+        S: signature::Signing<Signature = Vec<u8>>,
+    {
+        self.sign_with_mode(signer, signature::SignatureMode::default())
+    }
+
+    /// Like [`sign`](Self::sign), with the parts the signature spans chosen by `mode`.
+    ///
+    /// [`SignatureMode::HeaderOnly`](signature::SignatureMode::HeaderOnly) requires
+    /// the header to contain a payload digest, which packages built by this crate
+    /// and by rpm 4.14 and later do.
+    #[cfg(feature = "signature-meta")]
+    pub fn sign_with_mode<S>(
+        &mut self,
+        signer: S,
+        mode: signature::SignatureMode,
+    ) -> Result<(), RPMError>
+    where
+        S: signature::Signing<Signature = Vec<u8>>,
     {
         // create a temporary byte repr of the header
         // and re-create all hashes
         let mut header_bytes = Vec::<u8>::with_capacity(1024);
         self.metadata.header.write(&mut header_bytes)?;
 
-        let mut header_and_content_cursor =
-            SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]);
-
-        let digest_md5 = {
-            use md5::Digest;
-            let mut hasher = md5::Md5::default();
-            {
-                // avoid loading it into memory all at once
-                // since the content could be multiple 100s of MBs
-                let mut buf = [0u8; 256];
-                while let Ok(n) = header_and_content_cursor.read(&mut buf[..]) {
-                    hasher.update(&buf[0..n]);
-                }
-            }
-            let hash_result = hasher.finalize();
-            hash_result.to_vec()
-        };
+        if mode == signature::SignatureMode::HeaderOnly {
+            // refuse to sign a header that does not cover the payload
+            self.metadata.header.get_payload_digest()?;
+        }
 
-        header_and_content_cursor.seek(SeekFrom::Start(0))?;
+        let signature_spanning_header_only = signer.sign(header_bytes.as_slice())?;
 
-        let digest_sha1 = {
-            use sha1::Digest;
-            let mut hasher = sha1::Sha1::default();
-            hasher.update(&header_bytes);
-            let digest = hasher.finalize();
-            hex::encode(digest)
+        let signature_spanning_header_and_archive = match mode {
+            signature::SignatureMode::HeaderOnly => None,
+            signature::SignatureMode::HeaderAndPayload => Some(signer.sign(SeqCursor::new(&[
+                header_bytes.as_slice(),
+                self.content.as_slice(),
+            ]))?),
         };
 
-        let rsa_signature_spanning_header_only = signer.sign(header_bytes.as_slice())?;
+        self.replace_signature_header(
+            &header_bytes,
+            signer.algorithm(),
+            signature_spanning_header_only.as_slice(),
+            signature_spanning_header_and_archive.as_deref(),
+        )
+    }
 
-        let rsa_signature_spanning_header_and_archive =
-            signer.sign(&mut header_and_content_cursor)?;
+    /// The data signatures span, to sign it outside of this process, i.e. with an HSM.
+    ///
+    /// The produced signatures are added with [`apply_signatures`](Self::apply_signatures).
+    #[cfg(feature = "signature-meta")]
+    pub fn signing_data(&self) -> Result<SigningData<'_>, RPMError> {
+        let mut header = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header)?;
+        Ok(SigningData {
+            header,
+            payload: self.content.as_slice(),
+        })
+    }
 
-        // TODO FIXME verify this is the size we want, I don't think it is
-        // TODO maybe use signature_size instead of size
-        self.metadata.signature = Header::<IndexSignatureTag>::new_signature_header(
-            header_and_content_cursor.len() as i32,
-            &digest_md5,
-            digest_sha1,
-            rsa_signature_spanning_header_only.as_slice(),
-            rsa_signature_spanning_header_and_archive.as_slice(),
-        );
+    /// Add OpenPGP signatures made outside of this process over [`signing_data`](Self::signing_data).
+    ///
+    /// Without a signature spanning header and payload, the package is signed like with
+    /// [`SignatureMode::HeaderOnly`](signature::SignatureMode::HeaderOnly).
+    /// Each signature is verified with `verifier` before it is added.
+    #[cfg(feature = "signature-pgp")]
+    pub fn apply_signatures<V>(
+        &mut self,
+        verifier: V,
+        header_signature: &[u8],
+        header_and_payload_signature: Option<&[u8]>,
+    ) -> Result<(), RPMError>
+    where
+        V: signature::Verifying<Signature = Vec<u8>>,
+    {
+        let data = self.signing_data()?;
+        // refuse mixed up signatures before spending time on their verification
+        let algorithm = signature::pgp::check_signed_data(header_signature, data.header())?;
+        match header_and_payload_signature {
+            Some(header_and_payload_signature) => {
+                let other_algorithm = signature::pgp::check_signed_data(
+                    header_and_payload_signature,
+                    data.header_and_payload(),
+                )?;
+                if algorithm != other_algorithm {
+                    return Err(RPMError::InvalidDetachedSignature(
+                        "both signatures must be made with the same algorithm",
+                    ));
+                }
+                verifier.verify(data.header_and_payload(), header_and_payload_signature)?;
+            }
+            None => {
+                self.metadata.header.get_payload_digest()?;
+            }
+        }
+        verifier.verify(data.header(), header_signature)?;
+
+        let header_bytes = data.header;
+        self.replace_signature_header(
+            &header_bytes,
+            algorithm,
+            header_signature,
+            header_and_payload_signature,
+        )
+    }
+
+    /// Replace the signature header with one carrying the given signatures
+    /// and the digests that go along with them.
+    ///
+    /// Without a signature spanning header and payload, only a SHA-256 digest
+    /// of the header is added, otherwise the legacy MD5 and SHA-1 digests.
+    /// The signatures take their room from the reserved space.
+    #[cfg(feature = "signature-meta")]
+    fn replace_signature_header(
+        &mut self,
+        header_bytes: &[u8],
+        algorithm: signature::algorithm::AlgorithmType,
+        signature_spanning_header_only: &[u8],
+        signature_spanning_header_and_archive: Option<&[u8]>,
+    ) -> Result<(), RPMError> {
+        // RPMSIGTAG_SIZE spans the main header and the compressed payload
+        let size = (header_bytes.len() + self.content.len()) as u64;
+        let payload_size = self.metadata.signature.get_payload_size().ok();
+        // file signatures are made over the file digests of the unchanged header and payload
+        let file_signatures = self
+            .metadata
+            .signature
+            .index_entries
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.tag,
+                    IndexSignatureTag::RPMSIGTAG_FILESIGNATURES
+                        | IndexSignatureTag::RPMSIGTAG_FILESIGNATURE_LENGTH
+                        | IndexSignatureTag::RPMSIGTAG_VERITYSIGNATURES
+                        | IndexSignatureTag::RPMSIGTAG_VERITYSIGNATUREALGO
+                )
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let signature = match signature_spanning_header_and_archive {
+            None => {
+                let header_digest_sha256 = FileDigestAlgorithm::Sha2_256.digest(header_bytes)?;
+                let mut builder = Header::<IndexSignatureTag>::builder();
+                if let Some(payload_size) = payload_size {
+                    builder = builder.payload_size(payload_size);
+                }
+                builder
+                    .add_header_digest(hex::encode(header_digest_sha256).as_str())
+                    .add_header_signature(algorithm, signature_spanning_header_only)
+                    .build(size)
+            }
+            Some(signature_spanning_header_and_archive) => {
+                // hashed in parts, since the content could be multiple 100s of MBs
+                let digest_md5 =
+                    FileDigestAlgorithm::Md5.digest_parts(&[header_bytes, &self.content])?;
+                let digest_sha1 = hex::encode(FileDigestAlgorithm::Sha1.digest(header_bytes)?);
+                Header::<IndexSignatureTag>::new_signature_header(
+                    size,
+                    payload_size,
+                    &digest_md5,
+                    digest_sha1,
+                    algorithm,
+                    signature_spanning_header_only,
+                    signature_spanning_header_and_archive,
+                )
+            }
+        };
+        self.metadata.signature = signature
+            .with_entries(file_signatures)
+            .with_signature_reserved_space();
 
         Ok(())
     }
 
     /// Verify the signature as present within the RPM package.
     ///
+    /// Stops at the first failure, see [`verification_report`](Self::verification_report)
+    /// for the outcome of every signature and digest.
     ///
+    /// RSA signatures are looked up in `RPMSIGTAG_RSA` and `RPMSIGTAG_PGP`,
+    /// EdDSA and ECDSA signatures in `RPMSIGTAG_DSA` and `RPMSIGTAG_GPG`.
+    /// Without a signature spanning header and payload, the payload is checked
+    /// against the payload digest within the signed header.
     #[cfg(feature = "signature-meta")]
     pub fn verify_signature<V>(&self, verifier: V) -> Result<(), RPMError>
     where
-        V: signature::Verifying<signature::algorithm::RSA, Signature = Vec<u8>>,
+        V: signature::Verifying<Signature = Vec<u8>>,
     {
-        // TODO retval should be SIGNATURE_VERIFIED or MISMATCH, not just an error
-
         let mut header_bytes = Vec::<u8>::with_capacity(1024);
         self.metadata.header.write(&mut header_bytes)?;
 
-        let signature_header_only = self
-            .metadata
-            .signature
-            .get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_RSA)?;
+        let signature = &self.metadata.signature;
+        let algorithm = if signature
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_RSA)
+            .is_ok()
+        {
+            signature::algorithm::AlgorithmType::RSA
+        } else {
+            // EdDSA and ECDSA share their tags
+            signature::algorithm::AlgorithmType::EdDSA
+        };
+        let (header_only_tag, header_and_content_tag) = signature_tags(algorithm);
+
+        let signature_header_only = signature.get_entry_binary_data(header_only_tag)?;
 
         crate::signature::echo_signature("signature_header(header only)", signature_header_only);
 
-        let signature_header_and_content = self
-            .metadata
-            .signature
-            .get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_PGP)?;
+        if signature
+            .find_entry_or_err(&header_and_content_tag)
+            .is_err()
+        {
+            verifier.verify(header_bytes.as_slice(), signature_header_only)?;
+
+            let expected = self.metadata.header.get_payload_digest()?;
+            let actual = expected.algorithm().digest(&self.content)?;
+            let actual = FileDigest::load_from_str(expected.algorithm(), hex::encode(actual))?;
+            if actual != expected {
+                return Err(RPMError::PayloadDigestMismatch);
+            }
+            return Ok(());
+        }
+
+        let signature_header_and_content =
+            signature.get_entry_binary_data(header_and_content_tag)?;
 
         crate::signature::echo_signature(
             "signature_header(header and content)",
@@ -162,6 +309,267 @@ impl RPMPackage {
 
         Ok(())
     }
+
+    /// Check every signature and digest present within the RPM package.
+    ///
+    /// Mismatches are part of the report, errors are only returned
+    /// if the package itself cannot be processed.
+    #[cfg(feature = "signature-meta")]
+    pub fn verification_report<V>(
+        &self,
+        verifier: V,
+    ) -> Result<signature::VerificationReport, RPMError>
+    where
+        V: signature::Verifying<Signature = Vec<u8>>,
+    {
+        use signature::{CheckOutcome, DigestCheck, SignatureCheck, SignedRange};
+
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header_bytes)?;
+
+        fn digest_outcome<T: PartialEq + ?Sized>(expected: &T, actual: &T) -> CheckOutcome {
+            if expected == actual {
+                CheckOutcome::Valid
+            } else {
+                CheckOutcome::Invalid("digest mismatch".to_string())
+            }
+        }
+
+        let signature = &self.metadata.signature;
+        let mut report = signature::VerificationReport::default();
+
+        if let Ok(expected) = signature.get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_MD5) {
+            let actual = FileDigestAlgorithm::Md5.digest_parts(&[&header_bytes, &self.content])?;
+            report.digests.push(DigestCheck {
+                range: SignedRange::HeaderAndPayload,
+                algorithm: FileDigestAlgorithm::Md5,
+                outcome: digest_outcome(expected, actual.as_slice()),
+            });
+        }
+        for (tag, algorithm) in [
+            (IndexSignatureTag::RPMSIGTAG_SHA1, FileDigestAlgorithm::Sha1),
+            (
+                IndexSignatureTag::RPMSIGTAG_SHA256,
+                FileDigestAlgorithm::Sha2_256,
+            ),
+        ] {
+            if let Ok(expected) = signature.get_entry_string_data(tag) {
+                let actual = hex::encode(algorithm.digest(&header_bytes)?);
+                report.digests.push(DigestCheck {
+                    range: SignedRange::Header,
+                    algorithm,
+                    outcome: digest_outcome(expected, actual.as_str()),
+                });
+            }
+        }
+        if let Ok(expected) = self.metadata.header.get_payload_digest() {
+            let actual = expected.algorithm().digest(&self.content)?;
+            let actual = FileDigest::load_from_str(expected.algorithm(), hex::encode(actual))?;
+            report.digests.push(DigestCheck {
+                range: SignedRange::Payload,
+                algorithm: expected.algorithm(),
+                outcome: digest_outcome(&expected, &actual),
+            });
+        }
+
+        for (tag, range) in signature::SIGNATURE_TAGS {
+            if let Ok(data) = signature.get_entry_binary_data(tag) {
+                let result = match range {
+                    SignedRange::Header => verifier.verify(header_bytes.as_slice(), data),
+                    _ => verifier.verify(
+                        SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]),
+                        data,
+                    ),
+                };
+                report.signatures.push(SignatureCheck {
+                    tag,
+                    range,
+                    info: verifier.signature_info(data),
+                    outcome: CheckOutcome::from_result(result),
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Verify the IMA signature of each file against its digest within the header.
+    ///
+    /// Stops at the first file whose signature is missing or does not verify,
+    /// files without a digest like directories and symlinks carry no signature.
+    /// Only proves the file digests are signed, the header itself is covered by
+    /// the package signature checked with [`verify_signature`](Self::verify_signature).
+    #[cfg(feature = "signature-ima")]
+    pub fn verify_file_signatures(
+        &self,
+        verifier: &signature::ima::ImaVerifier,
+    ) -> Result<(), RPMError> {
+        let header = &self.metadata.header;
+        let algorithm = header.get_file_digest_algorithm().unwrap_or_default();
+        let paths = header.get_file_paths()?;
+        let digests = header.get_entry_string_array_data(IndexTag::RPMTAG_FILEDIGESTS)?;
+        let signatures = self.metadata.signature.get_file_ima_signatures()?;
+        if signatures.len() != digests.len() {
+            return Err(RPMError::InvalidImaSignature(
+                "the number of file signatures does not match the number of files",
+            ));
+        }
+
+        for ((path, digest), signature) in paths.iter().zip(digests).zip(signatures) {
+            if digest.is_empty() {
+                continue;
+            }
+            let result = if signature.is_empty() {
+                Err(RPMError::InvalidImaSignature("the file is not signed"))
+            } else {
+                hex::decode(digest)
+                    .and_then(|digest| Ok((digest, hex::decode(signature)?)))
+                    .map_err(RPMError::from)
+                    .and_then(|(digest, signature)| {
+                        verifier.verify_digest(algorithm, &digest, &signature)
+                    })
+            };
+            result.map_err(|e| RPMError::FileSignatureError {
+                path: path.display().to_string(),
+                source: Box::new(e),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Verify the fs-verity signature of each regular file against its content within the payload.
+    ///
+    /// Stops at the first file whose signature is missing or does not verify.
+    /// Like [`verify_file_signatures`](Self::verify_file_signatures), this does
+    /// not check the package signature.
+    #[cfg(feature = "signature-verity")]
+    pub fn verify_verity_signatures(
+        &self,
+        verifier: &signature::verity::VerityVerifier,
+    ) -> Result<(), RPMError> {
+        let algorithm = self
+            .metadata
+            .signature
+            .get_file_verity_signature_algorithm()?;
+        if algorithm != FS_VERITY_HASH_ALG_SHA256 {
+            return Err(RPMError::InvalidVeritySignature(
+                "only Merkle trees of SHA-256 digests are supported",
+            ));
+        }
+        let header = &self.metadata.header;
+        let paths = header.get_file_paths()?;
+        let modes = header.get_entry_i16_array_data(IndexTag::RPMTAG_FILEMODES)?;
+        let signatures = self.metadata.signature.get_file_verity_signatures()?;
+        if signatures.len() != paths.len() || modes.len() != paths.len() {
+            return Err(RPMError::InvalidVeritySignature(
+                "the number of file signatures does not match the number of files",
+            ));
+        }
+        // regular files and their signatures by their name within the payload
+        let mut unverified = paths
+            .iter()
+            .zip(modes)
+            .zip(signatures)
+            .filter(|((_, mode), _)| matches!(FileMode::from(*mode), FileMode::Regular { .. }))
+            .map(|((path, _), signature)| (format!(".{}", path.display()), signature))
+            .collect::<std::collections::BTreeMap<_, _>>();
+
+        let sizes = header.get_file_sizes()?;
+        let mut payload = super::compressor::decompress(
+            header.get_payload_compressor().unwrap_or("none"),
+            &self.content,
+        )?;
+        loop {
+            let mut magic = [0u8; 6];
+            payload.read_exact(&mut magic)?;
+            if magic == CPIO_STRIPPED_MAGIC.as_bytes() {
+                // packages with large files only refer to the index of the file in the header
+                // 8 hex digits, padded to 4 bytes like newc headers
+                let mut index = [0u8; 10];
+                payload.read_exact(&mut index)?;
+                let index = std::str::from_utf8(&index[..8])
+                    .ok()
+                    .and_then(|index| usize::from_str_radix(index, 16).ok())
+                    .filter(|index| *index < paths.len())
+                    .ok_or(RPMError::InvalidVeritySignature(
+                        "invalid file index in the payload",
+                    ))?;
+                let name = format!(".{}", paths[index].display());
+                let mut content = (&mut payload).take(sizes[index]);
+                if let Some(signature) = unverified.remove(&name) {
+                    Self::verify_verity_signature(verifier, &name, &mut content, signature)?;
+                }
+                std::io::copy(&mut content, &mut std::io::sink())?;
+                std::io::copy(
+                    &mut (&mut payload).take(cpio_padding(sizes[index]) as u64),
+                    &mut std::io::sink(),
+                )?;
+                continue;
+            }
+
+            let mut entry = cpio::newc::Reader::new(Read::chain(&magic[..], &mut payload))?;
+            if entry.entry().is_trailer() {
+                break;
+            }
+            let name = entry.entry().name().to_owned();
+            if let Some(signature) = unverified.remove(&name) {
+                Self::verify_verity_signature(verifier, &name, &mut entry, signature)?;
+            }
+            entry.finish()?;
+        }
+
+        match unverified.into_keys().next() {
+            Some(name) => Err(RPMError::FileSignatureError {
+                path: name.trim_start_matches('.').to_owned(),
+                source: Box::new(RPMError::InvalidVeritySignature(
+                    "the file is missing from the payload",
+                )),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "signature-verity")]
+    fn verify_verity_signature<R: Read>(
+        verifier: &signature::verity::VerityVerifier,
+        name: &str,
+        content: R,
+        signature: &str,
+    ) -> Result<(), RPMError> {
+        let result = if signature.is_empty() {
+            Err(RPMError::InvalidVeritySignature("the file is not signed"))
+        } else {
+            verifier.verify_reader(content, signature)
+        };
+        result.map_err(|e| RPMError::FileSignatureError {
+            path: name.trim_start_matches('.').to_owned(),
+            source: Box::new(e),
+        })
+    }
+}
+
+/// The data package signatures span, to sign it outside of this process.
+///
+/// Obtained with [`RPMPackage::signing_data`].
+#[cfg(feature = "signature-meta")]
+#[derive(Debug)]
+pub struct SigningData<'p> {
+    header: Vec<u8>,
+    payload: &'p [u8],
+}
+
+#[cfg(feature = "signature-meta")]
+impl<'p> SigningData<'p> {
+    /// The data of a header-only signature, stored in `RPMSIGTAG_RSA` or `RPMSIGTAG_DSA`.
+    pub fn header(&self) -> &[u8] {
+        self.header.as_slice()
+    }
+
+    /// The data of a signature spanning header and payload,
+    /// stored in `RPMSIGTAG_PGP` or `RPMSIGTAG_GPG`.
+    pub fn header_and_payload(&self) -> impl Read + '_ {
+        Read::chain(self.header.as_slice(), self.payload)
+    }
 }
 
 #[derive(PartialEq, Debug)]
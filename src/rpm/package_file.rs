@@ -0,0 +1,295 @@
+//! Signature operations on rpm files, without loading the payload into memory.
+
+use std::fs;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::headers::*;
+use super::Lead;
+use crate::constants::*;
+use crate::errors::*;
+use crate::signature;
+
+/// The signature tags of all algorithms.
+const SIGNATURE_TAGS: [IndexSignatureTag; 4] = [
+    IndexSignatureTag::RPMSIGTAG_RSA,
+    IndexSignatureTag::RPMSIGTAG_PGP,
+    IndexSignatureTag::RPMSIGTAG_DSA,
+    IndexSignatureTag::RPMSIGTAG_GPG,
+];
+
+/// An rpm file whose signature header is replaced, like `rpmsign` does.
+///
+/// The main header and the payload stay byte-identical, only the signature header changes.
+/// If the new signature header fits into the space of the old one, which is the case for
+/// packages carrying `RPMSIGTAG_RESERVEDSPACE`, [`write`](Self::write) rewrites it in place.
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let signing_key = Vec::<u8>::new();
+/// let signer = rpm::signature::pgp::Signer::load_from_asc_bytes(&signing_key)?;
+/// let mut file = rpm::RPMPackageFile::open("vendor-1.0.0-1.x86_64.rpm")?;
+/// file.sign(&signer)?;
+/// file.write()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RPMPackageFile {
+    path: PathBuf,
+    lead: Lead,
+    signature: Header<IndexSignatureTag>,
+    /// The number of bytes the signature header takes within the file.
+    signature_len: usize,
+    header: Header<IndexTag>,
+    /// The main header as found in the file, which is what signatures span.
+    header_bytes: Vec<u8>,
+}
+
+impl RPMPackageFile {
+    /// Read lead and headers of the rpm file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RPMError> {
+        let path = path.as_ref().to_path_buf();
+        let mut input = BufReader::new(fs::File::open(&path)?);
+
+        let mut lead_buffer = [0; LEAD_SIZE];
+        input.read_exact(&mut lead_buffer)?;
+        let lead = Lead::parse(&lead_buffer)?;
+        let signature = Header::parse_signature(&mut input)?;
+        let signature_len = signature.written_len();
+
+        // keep the raw bytes, parsing and writing the header again need not be lossless
+        let mut header_bytes = vec![0; 16];
+        input.read_exact(&mut header_bytes)?;
+        let index_header = IndexHeader::parse(&header_bytes)?;
+        header_bytes.resize(
+            16 + (index_header.header_size + index_header.num_entries * 16) as usize,
+            0,
+        );
+        input.read_exact(&mut header_bytes[16..])?;
+        let header = Header::parse(&mut header_bytes.as_slice())?;
+
+        Ok(RPMPackageFile {
+            path,
+            lead,
+            signature,
+            signature_len,
+            header,
+            header_bytes,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn lead(&self) -> &Lead {
+        &self.lead
+    }
+
+    /// The signature header, including changes not yet written.
+    pub fn signature(&self) -> &Header<IndexSignatureTag> {
+        &self.signature
+    }
+
+    pub fn header(&self) -> &Header<IndexTag> {
+        &self.header
+    }
+
+    /// Replace all signatures with one made by `signer`, like `rpmsign --resign`.
+    pub fn sign<S>(&mut self, signer: S) -> Result<(), RPMError>
+    where
+        S: signature::Signing,
+    {
+        self.sign_with_mode(signer, signature::SignatureMode::default())
+    }
+
+    /// Like [`sign`](Self::sign), with the parts the signature spans chosen by `mode`.
+    pub fn sign_with_mode<S>(
+        &mut self,
+        signer: S,
+        mode: signature::SignatureMode,
+    ) -> Result<(), RPMError>
+    where
+        S: signature::Signing,
+    {
+        self.add_signatures(signer, mode, &SIGNATURE_TAGS)
+    }
+
+    /// Add a signature made by `signer`, like `rpmsign --addsign`.
+    ///
+    /// Signatures made with other algorithms are kept, so a package can carry
+    /// i.e. an RSA and an EdDSA signature. rpm has room for a single signature
+    /// per algorithm, an existing one is replaced.
+    pub fn add_signature<S>(
+        &mut self,
+        signer: S,
+        mode: signature::SignatureMode,
+    ) -> Result<(), RPMError>
+    where
+        S: signature::Signing,
+    {
+        let (header_only_tag, header_and_archive_tag) = signature_tags(signer.algorithm());
+        self.add_signatures(signer, mode, &[header_only_tag, header_and_archive_tag])
+    }
+
+    /// Remove all signatures, like `rpmsign --delsign`. The digests are kept.
+    pub fn delete_signatures(&mut self) {
+        self.replace_entries(&SIGNATURE_TAGS, Vec::new());
+    }
+
+    /// Write the changed signature header back to the file.
+    ///
+    /// Returns whether the file was rewritten in place. Otherwise a copy with the new
+    /// signature header is written next to it, which then replaces the file.
+    pub fn write(&mut self) -> Result<bool, RPMError> {
+        if let Ok(signature) = self
+            .signature
+            .clone()
+            .with_reserved_space(self.signature_len)
+        {
+            let mut buf = Vec::with_capacity(self.signature_len);
+            signature.write_signature(&mut buf)?;
+            let mut out = fs::OpenOptions::new().write(true).open(&self.path)?;
+            out.seek(SeekFrom::Start(LEAD_SIZE as u64))?;
+            out.write_all(&buf)?;
+            out.sync_all()?;
+            return Ok(true);
+        }
+
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let tmp_path = self.path.with_file_name(format!(".{}.tmp", file_name));
+        let result = (|| -> Result<(), RPMError> {
+            let mut out = fs::File::create(&tmp_path)?;
+            out.set_permissions(fs::metadata(&self.path)?.permissions())?;
+            self.write_to(&mut out)?;
+            out.sync_all()?;
+            fs::rename(&tmp_path, &self.path)?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        self.signature_len = self.padded_signature().written_len();
+        Ok(false)
+    }
+
+    /// Write the rpm file with the changed signature header to `out`.
+    ///
+    /// The signature header gets [`SIGNATURE_RESERVED_SPACE`] bytes of room
+    /// for signatures added later on.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), RPMError> {
+        self.lead.write(out)?;
+        self.padded_signature().write_signature(out)?;
+        out.write_all(&self.header_bytes)?;
+        std::io::copy(&mut self.payload()?, out)?;
+        Ok(())
+    }
+
+    /// The signature header padded to leave room for [`SIGNATURE_RESERVED_SPACE`] bytes.
+    fn padded_signature(&self) -> Header<IndexSignatureTag> {
+        let len = self.signature.written_len() + SIGNATURE_RESERVED_SPACE;
+        match self.signature.clone().with_reserved_space(len) {
+            Ok(signature) | Err(signature) => signature,
+        }
+    }
+
+    /// A reader of the payload, which follows the main header in the file.
+    fn payload(&self) -> std::io::Result<impl Read> {
+        let mut input = fs::File::open(&self.path)?;
+        input.seek(SeekFrom::Start(
+            (LEAD_SIZE + self.signature_len + self.header_bytes.len()) as u64,
+        ))?;
+        Ok(BufReader::new(input))
+    }
+
+    /// Sign with `signer`, replacing the entries of `tags`, and add the
+    /// digests going along with the signatures unless present already.
+    fn add_signatures<S>(
+        &mut self,
+        signer: S,
+        mode: signature::SignatureMode,
+        tags: &[IndexSignatureTag],
+    ) -> Result<(), RPMError>
+    where
+        S: signature::Signing,
+    {
+        if mode == signature::SignatureMode::HeaderOnly {
+            // refuse to sign a header that does not cover the payload
+            self.header.get_payload_digest()?;
+        }
+
+        let (header_only_tag, header_and_archive_tag) = signature_tags(signer.algorithm());
+        let mut entries = vec![IndexEntry::new(
+            header_only_tag,
+            0i32, // filled by from_entries
+            IndexData::Bin(signer.sign(self.header_bytes.as_slice())?.as_ref().to_vec()),
+        )];
+
+        let digest_tags: &[IndexSignatureTag] = match mode {
+            signature::SignatureMode::HeaderAndPayload => {
+                let data = Read::chain(self.header_bytes.as_slice(), self.payload()?);
+                entries.push(IndexEntry::new(
+                    header_and_archive_tag,
+                    0i32, // filled by from_entries
+                    IndexData::Bin(signer.sign(data)?.as_ref().to_vec()),
+                ));
+                &[
+                    IndexSignatureTag::RPMSIGTAG_SHA1,
+                    IndexSignatureTag::RPMSIGTAG_MD5,
+                ]
+            }
+            signature::SignatureMode::HeaderOnly => &[IndexSignatureTag::RPMSIGTAG_SHA256],
+        };
+        for tag in digest_tags {
+            if self.signature.find_entry_or_err(tag).is_ok() {
+                continue;
+            }
+            let data = match tag {
+                IndexSignatureTag::RPMSIGTAG_MD5 => {
+                    IndexData::Bin(FileDigestAlgorithm::Md5.digest_reader(Read::chain(
+                        self.header_bytes.as_slice(),
+                        self.payload()?,
+                    ))?)
+                }
+                IndexSignatureTag::RPMSIGTAG_SHA1 => IndexData::StringTag(hex::encode(
+                    FileDigestAlgorithm::Sha1.digest(&self.header_bytes)?,
+                )),
+                _ => IndexData::StringTag(hex::encode(
+                    FileDigestAlgorithm::Sha2_256.digest(&self.header_bytes)?,
+                )),
+            };
+            entries.push(IndexEntry::new(*tag, 0i32, data));
+        }
+
+        self.replace_entries(tags, entries);
+        Ok(())
+    }
+
+    /// Remove the entries of `tags` from the signature header and append `entries`.
+    fn replace_entries(
+        &mut self,
+        tags: &[IndexSignatureTag],
+        entries: Vec<IndexEntry<IndexSignatureTag>>,
+    ) {
+        let mut kept = self
+            .signature
+            .clone()
+            .index_entries
+            .into_iter()
+            .filter(|entry| {
+                entry.tag != IndexSignatureTag::HEADER_SIGNATURES
+                    && entry.tag != IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE
+                    && !tags.contains(&entry.tag)
+            })
+            .collect::<Vec<_>>();
+        kept.extend(entries);
+        self.signature = Header::from_entries(kept, IndexSignatureTag::HEADER_SIGNATURES);
+    }
+}
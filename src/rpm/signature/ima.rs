@@ -0,0 +1,221 @@
+//! IMA file signatures, which the kernel's integrity measurement architecture
+//! checks before files are executed or opened.
+//!
+//! rpm installs them as the `security.ima` extended attribute of each file.
+//! A signature is made over the file digest recorded in the header, in the
+//! digital signature v2 format of ima-evm-utils:
+//!
+//! | bytes | content                                         |
+//! |-------|-------------------------------------------------|
+//! | 1     | type, `0x03` for a digital signature            |
+//! | 1     | version, `0x02`                                 |
+//! | 1     | hash algorithm of the file digest               |
+//! | 4     | key id, the last 4 bytes of the key identifier  |
+//! | 2     | big endian length of the signature              |
+//! | n     | PKCS#1 v1.5 RSA or DER encoded ECDSA signature  |
+
+use super::x509::{pem_str, Certificate, SigningKey, VerifyingKey};
+use crate::errors::*;
+use crate::{FileDigestAlgorithm, Header, IndexTag};
+
+use std::convert::TryInto;
+
+const EVM_IMA_XATTR_DIGSIG: u8 = 0x03;
+const DIGSIG_VERSION_2: u8 = 0x02;
+const DIGSIG_HEADER_LEN: usize = 9;
+
+/// The id of a digest algorithm within the kernel, `HASH_ALGO_*` of `hash_info.h`.
+fn kernel_hash_algo(algorithm: FileDigestAlgorithm) -> Result<u8, RPMError> {
+    Ok(match algorithm {
+        FileDigestAlgorithm::Md5 => 1,
+        FileDigestAlgorithm::Sha1 => 2,
+        FileDigestAlgorithm::Sha2_256 => 4,
+        FileDigestAlgorithm::Sha2_384 => 5,
+        FileDigestAlgorithm::Sha2_512 => 6,
+        FileDigestAlgorithm::Sha2_224 => 7,
+        algorithm => return Err(RPMError::UnsupportedFileDigestAlgorithm(algorithm)),
+    })
+}
+
+/// The key id of a public key, as calculated by ima-evm-utils and by
+/// openssl for the subject key identifier of a certificate.
+fn key_id(subject_public_key: &[u8]) -> Result<[u8; 4], RPMError> {
+    let digest = FileDigestAlgorithm::Sha1.digest(subject_public_key)?;
+    Ok(digest[16..]
+        .try_into()
+        .expect("SHA-1 digests are 20 bytes long"))
+}
+
+/// Signs file digests with an IMA/EVM private key.
+///
+/// Used with [`RPMBuilder::sign_files`](crate::RPMBuilder::sign_files) to add
+/// `RPMSIGTAG_FILESIGNATURES` to built packages.
+#[derive(Debug)]
+pub struct ImaSigner {
+    key: SigningKey,
+    key_id: [u8; 4],
+}
+
+impl ImaSigner {
+    /// Load a PEM encoded private key, PKCS#8, PKCS#1 for RSA or SEC1 for ECDSA keys.
+    pub fn load_from_pem(input: &str) -> Result<Self, RPMError> {
+        let key = SigningKey::load_from_pem(input)?;
+        let key_id = key_id(&key.subject_public_key()?)?;
+        Ok(Self { key, key_id })
+    }
+
+    pub fn load_from_pem_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        Self::load_from_pem(pem_str(input)?)
+    }
+
+    /// The key id within the signatures as lowercase hex, i.e. `1adfaa0e`.
+    pub fn key_id(&self) -> String {
+        hex::encode(self.key_id)
+    }
+
+    /// Sign a file digest, returning the content of the `security.ima` extended attribute.
+    pub fn sign_digest(
+        &self,
+        algorithm: FileDigestAlgorithm,
+        digest: &[u8],
+    ) -> Result<Vec<u8>, RPMError> {
+        let hash_algo = kernel_hash_algo(algorithm)?;
+        let signature = self.key.sign_digest(algorithm, digest)?;
+
+        let mut xattr = Vec::with_capacity(DIGSIG_HEADER_LEN + signature.len());
+        xattr.extend_from_slice(&[EVM_IMA_XATTR_DIGSIG, DIGSIG_VERSION_2, hash_algo]);
+        xattr.extend_from_slice(&self.key_id);
+        xattr.extend_from_slice(&(signature.len() as u16).to_be_bytes());
+        xattr.extend_from_slice(&signature);
+        Ok(xattr)
+    }
+
+    /// Sign the file digests of a header, in the order of its files.
+    ///
+    /// Files without a digest, i.e. directories and symlinks, get an empty signature.
+    pub(crate) fn sign_file_digests(
+        &self,
+        header: &Header<IndexTag>,
+    ) -> Result<Vec<String>, RPMError> {
+        let algorithm = header.get_file_digest_algorithm().unwrap_or_default();
+        header
+            .get_entry_string_array_data(IndexTag::RPMTAG_FILEDIGESTS)?
+            .iter()
+            .map(|digest| {
+                if digest.is_empty() {
+                    return Ok(String::new());
+                }
+                let digest = hex::decode(digest)?;
+                Ok(hex::encode(self.sign_digest(algorithm, &digest)?))
+            })
+            .collect()
+    }
+}
+
+/// Checks IMA file signatures with the public key of an X.509 certificate.
+#[derive(Debug)]
+pub struct ImaVerifier {
+    key: VerifyingKey,
+    key_id: [u8; 4],
+}
+
+impl ImaVerifier {
+    /// Load a PEM or DER encoded X.509 certificate, as loaded into the kernel's `.ima` keyring.
+    ///
+    /// The key id is taken from the subject key identifier, like the kernel does,
+    /// or calculated from the public key if the certificate has none.
+    pub fn load_from_certificate(input: &[u8]) -> Result<Self, RPMError> {
+        let certificate = Certificate::load(input)?;
+        let key_id = match certificate.subject_key_identifier {
+            Some(id) if id.len() >= 4 => id[id.len() - 4..].try_into().unwrap(),
+            _ => key_id(&certificate.subject_public_key)?,
+        };
+        Ok(Self {
+            key: certificate.key,
+            key_id,
+        })
+    }
+
+    /// The key id of the certificate as lowercase hex, i.e. `1adfaa0e`.
+    pub fn key_id(&self) -> String {
+        hex::encode(self.key_id)
+    }
+
+    /// Check a `security.ima` signature of a file with the given digest.
+    ///
+    /// Signatures of other keys yield [`RPMError::KeyNotFoundError`].
+    pub fn verify_digest(
+        &self,
+        algorithm: FileDigestAlgorithm,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<(), RPMError> {
+        if signature.len() < DIGSIG_HEADER_LEN
+            || signature[0] != EVM_IMA_XATTR_DIGSIG
+            || signature[1] != DIGSIG_VERSION_2
+        {
+            return Err(RPMError::InvalidImaSignature(
+                "expected a digital signature of version 2",
+            ));
+        }
+        if signature[2] != kernel_hash_algo(algorithm)? {
+            return Err(RPMError::InvalidImaSignature(
+                "the signature is made over a digest of another algorithm",
+            ));
+        }
+        if signature[3..7] != self.key_id {
+            return Err(RPMError::KeyNotFoundError {
+                key_ref: hex::encode(&signature[3..7]),
+            });
+        }
+        let len = u16::from_be_bytes([signature[7], signature[8]]) as usize;
+        let signature = &signature[DIGSIG_HEADER_LEN..];
+        if signature.len() != len {
+            return Err(RPMError::InvalidImaSignature(
+                "the signature length does not match",
+            ));
+        }
+
+        self.key
+            .verify_digest(algorithm, digest, signature)
+            .map_err(|source| RPMError::VerificationError {
+                source,
+                key_ref: self.key_id(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::x509::test::load_keys;
+
+    #[test]
+    fn sign_and_verify_digest() -> Result<(), RPMError> {
+        let digest = FileDigestAlgorithm::Sha2_256.digest(b"some file content")?;
+        let other_digest = FileDigestAlgorithm::Sha2_256.digest(b"other file content")?;
+        let [(rsa_key, rsa_cert), (ecdsa_key, ecdsa_cert)] = load_keys();
+        for (key, cert, key_id) in [
+            (rsa_key, rsa_cert, "d687c9e0"),
+            (ecdsa_key, ecdsa_cert, "dc856de7"),
+        ] {
+            let signer = ImaSigner::load_from_pem_bytes(&key)?;
+            let verifier = ImaVerifier::load_from_certificate(&cert)?;
+            assert_eq!(key_id, signer.key_id());
+            assert_eq!(key_id, verifier.key_id());
+
+            let signature = signer.sign_digest(FileDigestAlgorithm::Sha2_256, &digest)?;
+            assert_eq!(
+                [0x03, 0x02, 0x04],
+                signature[..3],
+                "digital signature v2 over a SHA-256 digest"
+            );
+            verifier.verify_digest(FileDigestAlgorithm::Sha2_256, &digest, &signature)?;
+            assert!(matches!(
+                verifier.verify_digest(FileDigestAlgorithm::Sha2_256, &other_digest, &signature),
+                Err(RPMError::VerificationError { .. })
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,21 @@
 mod traits;
 pub use self::traits::*;
 
+mod report;
+pub use self::report::*;
+
 #[cfg(feature = "signature-pgp")]
 pub mod pgp;
 
+#[cfg(any(feature = "signature-ima", feature = "signature-verity"))]
+pub(crate) mod x509;
+
+#[cfg(feature = "signature-ima")]
+pub mod ima;
+
+#[cfg(feature = "signature-verity")]
+pub mod verity;
+
 /// test helper to print signatures
 pub fn echo_signature(scope: &str, signature: &[u8]) {
     log::debug!(
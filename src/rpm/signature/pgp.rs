@@ -3,15 +3,235 @@ use crate::errors::RPMError;
 
 use std::io::{Cursor, Read};
 
+use ::pgp::crypto::ecc_curve::ECCCurve;
+use ::pgp::crypto::public_key::PublicKeyAlgorithm;
+use ::pgp::types::{EncryptedSecretParams, Mpi, PlainSecretParams, PublicParams, SecretParams};
 use ::pgp::{composed::Deserializable, types::KeyTrait};
 
 use ::pgp::packet::*;
 
+use num_traits::FromPrimitive;
+use p256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+
 fn now() -> ::chrono::DateTime<::chrono::Utc> {
     // accuracy of serialized format is only down to seconds
     use ::chrono::offset::TimeZone;
     let now = ::chrono::offset::Utc::now();
-    ::chrono::offset::Utc.timestamp(now.timestamp(), 0u32)
+    ::chrono::offset::Utc
+        .timestamp_opt(now.timestamp(), 0u32)
+        .unwrap()
+}
+
+/// The algorithm of a key, if rpm supports signatures made with it.
+fn algorithm_type(algorithm: PublicKeyAlgorithm) -> Option<traits::algorithm::AlgorithmType> {
+    use traits::algorithm::AlgorithmType;
+    match algorithm {
+        PublicKeyAlgorithm::RSA | PublicKeyAlgorithm::RSASign => Some(AlgorithmType::RSA),
+        PublicKeyAlgorithm::EdDSA => Some(AlgorithmType::EdDSA),
+        PublicKeyAlgorithm::ECDSA => Some(AlgorithmType::ECDSA),
+        _ => None,
+    }
+}
+
+/// Hash the data and the signature metadata like the `pgp` crate does before signing.
+fn signature_digest<R: Read>(config: &SignatureConfig, data: R) -> ::pgp::errors::Result<Vec<u8>> {
+    let mut hasher = config.hash_alg.new_hasher()?;
+    config.hash_data_to_sign(&mut *hasher, data)?;
+    let len = config.hash_signature_data(&mut *hasher)?;
+    hasher.update(&config.trailer(len));
+    Ok(hasher.finish())
+}
+
+/// Quick sanity check that an OpenPGP signature packet was made over `data`, without a key.
+///
+/// Only the leading 16 bits of the digest recorded in the packet can be compared, so this
+/// catches mixed up signatures but does not authenticate anything. The signature still has
+/// to be verified with the public key to be trusted.
+pub fn check_signed_data<R: Read>(
+    signature: &[u8],
+    data: R,
+) -> Result<traits::algorithm::AlgorithmType, RPMError> {
+    let signature = Verifier::parse_signature(signature)?;
+    let algorithm = algorithm_type(signature.config.pub_alg).ok_or_else(|| {
+        RPMError::UnsupportedKeyAlgorithm(format!("{:?}", signature.config.pub_alg))
+    })?;
+    let digest =
+        signature_digest(&signature.config, data).map_err(|e| RPMError::SignError(Box::new(e)))?;
+    if signature.signed_hash_value != digest[0..2] {
+        return Err(RPMError::InvalidDetachedSignature(
+            "signature packet was not made over the package data",
+        ));
+    }
+    Ok(algorithm)
+}
+
+/// Left pad a big endian integer to the size of a P-256 field element.
+fn p256_field_bytes(mpi: &[u8]) -> Option<p256::FieldBytes> {
+    let mut bytes = p256::FieldBytes::default();
+    let offset = bytes.len().checked_sub(mpi.len())?;
+    bytes[offset..].copy_from_slice(mpi);
+    Some(bytes)
+}
+
+fn ensure_p256(params: &PublicParams) -> Result<(), RPMError> {
+    match params {
+        PublicParams::ECDSA {
+            curve: ECCCurve::P256,
+            ..
+        } => Ok(()),
+        PublicParams::ECDSA { curve, .. } => Err(RPMError::UnsupportedKeyAlgorithm(format!(
+            "ECDSA with curve {}",
+            curve.name()
+        ))),
+        _ => Err(RPMError::UnsupportedKeyAlgorithm(
+            "ECDSA without curve parameters".to_string(),
+        )),
+    }
+}
+
+/// The length of the public part of a v4 secret key packet, if the algorithm is known.
+fn public_key_len(body: &[u8]) -> Option<usize> {
+    let mpi = |offset: usize| -> Option<usize> {
+        let bits = u16::from_be_bytes([*body.get(offset)?, *body.get(offset + 1)?]) as usize;
+        Some(offset + 2 + (bits + 7) / 8)
+    };
+    // a length octet followed by as many octets, like curve OIDs and KDF parameters
+    let field = |offset: usize| -> Option<usize> { Some(offset + 1 + *body.get(offset)? as usize) };
+    // version, creation time, algorithm
+    let offset = 6;
+    let end = match PublicKeyAlgorithm::from_u8(*body.get(5)?)? {
+        PublicKeyAlgorithm::RSA | PublicKeyAlgorithm::RSAEncrypt | PublicKeyAlgorithm::RSASign => {
+            mpi(mpi(offset)?)?
+        }
+        PublicKeyAlgorithm::DSA => mpi(mpi(mpi(mpi(offset)?)?)?)?,
+        PublicKeyAlgorithm::Elgamal | PublicKeyAlgorithm::ElgamalSign => mpi(mpi(mpi(offset)?)?)?,
+        PublicKeyAlgorithm::ECDSA | PublicKeyAlgorithm::EdDSA => mpi(field(offset)?)?,
+        PublicKeyAlgorithm::ECDH => field(mpi(field(offset)?)?)?,
+        _ => return None,
+    };
+    Some(end).filter(|end| *end <= body.len())
+}
+
+/// The `pgp` crate can not parse secret keys protected with S2K usage 255, whose
+/// key material is followed by a two octet checksum instead of a SHA-1 hash.
+///
+/// Rewrite them to usage 254 in place, which changes nothing but the expected
+/// checksum. Returns whether the primary key was rewritten, [`check_passphrase`]
+/// has to verify the two octet checksum then.
+///
+/// Only keys encrypted with AES and an iterated and salted S2K, which is what
+/// gpg produces, are rewritten, other forms of usage 255 are refused.
+fn rewrite_checksum_protection(packets: &mut [u8]) -> Result<bool, RPMError> {
+    let mut primary_key = false;
+    let mut offset = 0;
+    while let Some(&ctb) = packets.get(offset) {
+        let (tag, header_len, len) = if ctb & 0x40 != 0 {
+            match packets.get(offset + 1).copied() {
+                Some(len @ 0..=191) => (ctb & 0x3f, 2, len as usize),
+                Some(len @ 192..=223) => match packets.get(offset + 2) {
+                    Some(&low) => (
+                        ctb & 0x3f,
+                        3,
+                        ((len as usize - 192) << 8) + low as usize + 192,
+                    ),
+                    None => break,
+                },
+                Some(255) => match packets.get(offset + 2..offset + 6) {
+                    Some(len) => (
+                        ctb & 0x3f,
+                        6,
+                        u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize,
+                    ),
+                    None => break,
+                },
+                // partial body lengths are not used for keys
+                _ => break,
+            }
+        } else {
+            let len_octets = match ctb & 0x03 {
+                0 => 1,
+                1 => 2,
+                2 => 4,
+                _ => break,
+            };
+            match packets.get(offset + 1..offset + 1 + len_octets) {
+                Some(len) => (
+                    (ctb >> 2) & 0x0f,
+                    1 + len_octets,
+                    len.iter()
+                        .fold(0, |acc, octet| (acc << 8) | *octet as usize),
+                ),
+                None => break,
+            }
+        };
+        let body_start = offset + header_len;
+        let body = match packets.get_mut(body_start..body_start + len) {
+            Some(body) => body,
+            None => break,
+        };
+        // secret key and secret subkey packets of version 4
+        if (tag == 5 || tag == 7) && body.first() == Some(&4) {
+            if let Some(len) = public_key_len(body) {
+                if body.get(len) == Some(&255) {
+                    // symmetric algorithm AES-128, AES-192 or AES-256, followed by S2K type 3
+                    if !matches!(body.get(len + 1..len + 3), Some([7..=9, 3])) {
+                        return Err(RPMError::UnsupportedKeyProtection(
+                            "S2K usage 255 is only supported with AES and an iterated and salted S2K",
+                        ));
+                    }
+                    body[len] = 254;
+                    primary_key |= tag == 5;
+                }
+            }
+        }
+        offset = body_start + len;
+    }
+    Ok(primary_key)
+}
+
+/// Check a passphrase by decrypting the secret key material,
+/// the `pgp` crate does not verify the checksum when unlocking.
+fn check_passphrase(
+    params: &EncryptedSecretParams,
+    s2k_usage: u8,
+    algorithm: PublicKeyAlgorithm,
+    passphrase: &str,
+) -> Result<(), RPMError> {
+    let sign_error = |e: ::pgp::errors::Error| RPMError::SignError(Box::new(e));
+    let key = params
+        .string_to_key()
+        .derive_key(passphrase, params.encryption_algorithm().key_size())
+        .map_err(sign_error)?;
+    let mut plaintext = params.data().to_vec();
+    params
+        .encryption_algorithm()
+        .decrypt_with_iv_regular(&key, params.iv(), &mut plaintext)
+        .map_err(sign_error)?;
+
+    let valid = match s2k_usage {
+        // the SHA-1 of the secret key material is encrypted along with it
+        254 if plaintext.len() >= 20 => {
+            use sha1::Digest;
+            let (secret, checksum) = plaintext.split_at(plaintext.len() - 20);
+            sha1::Sha1::digest(secret).as_slice() == checksum
+        }
+        254 => false,
+        // otherwise the sum of the secret key material octets, modulo 65536 and big endian
+        _ if plaintext.len() >= 2 => {
+            let (secret, checksum) = plaintext.split_at(plaintext.len() - 2);
+            let sum = secret
+                .iter()
+                .fold(0u16, |sum, octet| sum.wrapping_add(*octet as u16));
+            sum.to_be_bytes() == checksum
+                && PlainSecretParams::from_slice(&plaintext, algorithm).is_ok()
+        }
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(RPMError::WrongPassphrase)
+    }
 }
 
 /// Signer implementation using the `pgp` crate.
@@ -19,26 +239,53 @@ fn now() -> ::chrono::DateTime<::chrono::Utc> {
 /// Note that this only supports ascii armored key files
 /// commonly with the file extension `.asc` as generated
 /// by i.e. `gpg`.
-#[derive(Clone, Debug)]
+///
+/// RSA, EdDSA (Ed25519) and ECDSA (NIST P-256) keys are supported,
+/// the signature algorithm follows the loaded key. Passphrase protected
+/// keys are unlocked at signing time, see [`with_passphrase`](Self::with_passphrase).
+#[derive(Clone)]
 pub struct Signer {
     secret_key: ::pgp::composed::signed_key::SignedSecretKey,
+    signing_time: Option<u32>,
+    passphrase_fn: Option<std::sync::Arc<dyn Fn() -> String + Send + Sync>>,
+    /// The primary key was protected with S2K usage 255, see [`rewrite_checksum_protection`].
+    checksum_protected: bool,
 }
 
-impl traits::Signing<traits::algorithm::RSA> for Signer {
+impl std::fmt::Debug for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Signer")
+            .field("secret_key", &self.secret_key)
+            .field("signing_time", &self.signing_time)
+            .field("passphrase_fn", &self.passphrase_fn.as_ref().map(|_| ".."))
+            .field("checksum_protected", &self.checksum_protected)
+            .finish()
+    }
+}
+
+impl traits::Signing for Signer {
     type Signature = Vec<u8>;
 
     /// Despite the fact the API suggest zero copy pattern,
     /// it internally creates a copy until crate `pgp` provides
     /// a `Read` based implementation.
     fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
-        let passwd_fn = String::new;
+        let passphrase = self.passphrase()?;
 
-        let now = now();
+        let now = match self.signing_time {
+            Some(timestamp) => {
+                use ::chrono::offset::TimeZone;
+                ::chrono::offset::Utc
+                    .timestamp_opt(timestamp as i64, 0u32)
+                    .unwrap()
+            }
+            None => now(),
+        };
 
         let sig_cfg = SignatureConfig {
             version: SignatureVersion::V4,
             typ: SignatureType::Binary,
-            pub_alg: ::pgp::crypto::public_key::PublicKeyAlgorithm::RSA,
+            pub_alg: self.secret_key.algorithm(),
             hash_alg: ::pgp::crypto::hash::HashAlgorithm::SHA2_256,
             issuer: Some(self.secret_key.key_id()),
             created: Some(now),
@@ -46,13 +293,22 @@ impl traits::Signing<traits::algorithm::RSA> for Signer {
             hashed_subpackets: vec![
                 Subpacket::SignatureCreationTime(now),
                 Subpacket::Issuer(self.secret_key.key_id()),
+                // like gpg does, so the key can be told apart by more than its id
+                Subpacket::IssuerFingerprint(
+                    ::pgp::types::KeyVersion::V4,
+                    self.secret_key.fingerprint().into(),
+                ),
                 //::pgp::packet::Subpacket::SignersUserID("rpm"), TODO this would be a nice addition
             ],
         };
 
-        let signature_packet = sig_cfg
-            .sign(&self.secret_key, passwd_fn, data)
-            .map_err(|e| RPMError::SignError(Box::new(e)))?;
+        let signature_packet = match sig_cfg.pub_alg {
+            // not implemented by the `pgp` crate
+            PublicKeyAlgorithm::ECDSA => self.sign_ecdsa(sig_cfg, passphrase, data)?,
+            _ => sig_cfg
+                .sign(&self.secret_key, || passphrase, data)
+                .map_err(|e| RPMError::SignError(Box::new(e)))?,
+        };
 
         let mut signature_bytes = Vec::with_capacity(1024);
         let mut cursor = Cursor::new(&mut signature_bytes);
@@ -61,9 +317,94 @@ impl traits::Signing<traits::algorithm::RSA> for Signer {
 
         Ok(signature_bytes)
     }
+
+    fn algorithm(&self) -> traits::algorithm::AlgorithmType {
+        // checked when loading the key
+        algorithm_type(self.secret_key.algorithm()).expect("supported key algorithm")
+    }
 }
 
 impl Signer {
+    fn sign_ecdsa<R: Read>(
+        &self,
+        config: SignatureConfig,
+        passphrase: String,
+        data: R,
+    ) -> Result<::pgp::packet::Signature, RPMError> {
+        let key = &self.secret_key.primary_key;
+        ensure_p256(key.public_params())?;
+        let secret = match key.secret_params() {
+            SecretParams::Plain(params) => params.clone(),
+            SecretParams::Encrypted(params) => params
+                .unlock(|| passphrase, PublicKeyAlgorithm::ECDSA)
+                .map_err(|e| RPMError::SignError(Box::new(e)))?,
+        };
+        let scalar = match &secret {
+            PlainSecretParams::ECDSA(scalar) => p256_field_bytes(scalar.as_bytes()),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            RPMError::UnsupportedKeyAlgorithm("ECDSA key without a P-256 secret".to_string())
+        })?;
+        let signing_key = p256::ecdsa::SigningKey::from_bytes(&scalar)
+            .map_err(|e| RPMError::SignError(Box::new(e)))?;
+
+        let digest =
+            signature_digest(&config, data).map_err(|e| RPMError::SignError(Box::new(e)))?;
+        let signature: p256::ecdsa::Signature = signing_key
+            .sign_prehash(&digest)
+            .map_err(|e| RPMError::SignError(Box::new(e)))?;
+        let (r, s) = signature.split_bytes();
+        Ok(::pgp::packet::Signature::from_config(
+            config,
+            [digest[0], digest[1]],
+            vec![Mpi::from_raw_slice(&r), Mpi::from_raw_slice(&s)],
+        ))
+    }
+
+    /// Use a fixed signature creation time instead of the current time,
+    /// i.e. the `SOURCE_DATE_EPOCH` of a reproducible build.
+    pub fn with_signing_time(mut self, timestamp: u32) -> Self {
+        self.signing_time = Some(timestamp);
+        self
+    }
+
+    /// Unlock a passphrase protected secret key with `passphrase`.
+    pub fn with_passphrase<P: Into<String>>(self, passphrase: P) -> Self {
+        let passphrase = passphrase.into();
+        self.with_passphrase_fn(move || passphrase.clone())
+    }
+
+    /// Unlock a passphrase protected secret key with the passphrase returned by `passphrase_fn`.
+    ///
+    /// It is called each time a signature is made, so the passphrase
+    /// does not have to be held in memory, i.e. when asking an agent.
+    pub fn with_passphrase_fn<F>(mut self, passphrase_fn: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.passphrase_fn = Some(std::sync::Arc::new(passphrase_fn));
+        self
+    }
+
+    /// The passphrase for the secret key, checked against the key if it is protected.
+    fn passphrase(&self) -> Result<String, RPMError> {
+        let passphrase = self
+            .passphrase_fn
+            .as_ref()
+            .map(|passphrase_fn| passphrase_fn())
+            .unwrap_or_default();
+        if let SecretParams::Encrypted(params) = self.secret_key.primary_key.secret_params() {
+            let s2k_usage = if self.checksum_protected {
+                255
+            } else {
+                params.string_to_key_id()
+            };
+            check_passphrase(params, s2k_usage, self.secret_key.algorithm(), &passphrase)?;
+        }
+        Ok(passphrase)
+    }
+
     /// load the private key for signing
     pub fn load_from_asc_bytes(input: &[u8]) -> Result<Self, RPMError> {
         // only asc loading is supported right now
@@ -75,12 +416,30 @@ impl Signer {
     }
 
     pub fn load_from_asc(input: &str) -> Result<Self, RPMError> {
-        let (secret_key, _) = ::pgp::composed::signed_key::SignedSecretKey::from_string(input)
-            .map_err(|e| RPMError::KeyLoadError {
-                source: Box::new(e),
-                details: "Failed to parse bytes as ascii armored key",
-            })?;
-        Ok(Self { secret_key })
+        let load_error = |e: ::pgp::errors::Error| RPMError::KeyLoadError {
+            source: Box::new(e),
+            details: "Failed to parse bytes as ascii armored key",
+        };
+        let mut packets = Vec::new();
+        ::pgp::armor::Dearmor::new(Cursor::new(input.as_bytes()))
+            .read_to_end(&mut packets)
+            .map_err(|e| load_error(e.into()))?;
+        let checksum_protected = rewrite_checksum_protection(&mut packets)?;
+        let secret_key =
+            ::pgp::composed::signed_key::SignedSecretKey::from_bytes(packets.as_slice())
+                .map_err(load_error)?;
+        if algorithm_type(secret_key.algorithm()).is_none() {
+            return Err(RPMError::UnsupportedKeyAlgorithm(format!(
+                "{:?}",
+                secret_key.algorithm()
+            )));
+        }
+        Ok(Self {
+            secret_key,
+            signing_time: None,
+            passphrase_fn: None,
+            checksum_protected,
+        })
     }
 }
 
@@ -107,83 +466,215 @@ impl Verifier {
             .ok_or(RPMError::NoSignatureFound)?;
         Ok(signature)
     }
+
+    /// Verify with a single key, the `pgp` crate does not implement ECDSA.
+    fn verify_with_key<K, R>(
+        signature: &::pgp::packet::Signature,
+        key: &K,
+        params: &PublicParams,
+        data: R,
+    ) -> Result<(), RPMError>
+    where
+        K: ::pgp::types::PublicKeyTrait,
+        R: Read,
+    {
+        let verification_error = |e: Box<dyn std::error::Error>| RPMError::VerificationError {
+            source: e,
+            key_ref: format!("{:?}", key.key_id()),
+        };
+        if signature.config.pub_alg != PublicKeyAlgorithm::ECDSA {
+            return signature
+                .verify(key, data)
+                .map_err(|e| verification_error(Box::new(e)));
+        }
+
+        ensure_p256(params)?;
+        let point = match params {
+            PublicParams::ECDSA { p, .. } => p,
+            _ => unreachable!("checked to be ECDSA"),
+        };
+        let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(point.as_bytes())
+            .map_err(|e| verification_error(Box::new(e)))?;
+        let ecdsa_signature = match signature.signature.as_slice() {
+            [r, s] => p256_field_bytes(r.as_bytes())
+                .zip(p256_field_bytes(s.as_bytes()))
+                .ok_or_else(|| {
+                    verification_error("ECDSA signature exceeding P-256".to_string().into())
+                })
+                .and_then(|(r, s)| {
+                    p256::ecdsa::Signature::from_scalars(r, s)
+                        .map_err(|e| verification_error(Box::new(e)))
+                })?,
+            _ => {
+                return Err(verification_error(
+                    "ECDSA signature without r and s".to_string().into(),
+                ))
+            }
+        };
+        let digest = signature_digest(&signature.config, data)
+            .map_err(|e| verification_error(Box::new(e)))?;
+        if signature.signed_hash_value != digest[0..2] {
+            return Err(verification_error(
+                "invalid signed hash value".to_string().into(),
+            ));
+        }
+        verifying_key
+            .verify_prehash(&digest, &ecdsa_signature)
+            .map_err(|e| verification_error(Box::new(e)))
+    }
 }
 
-impl traits::Verifying<traits::algorithm::RSA> for Verifier {
+/// Verify with the primary key or the subkey of `public_key` that issued the signature.
+fn verify_with_public_key<R: Read>(
+    public_key: &::pgp::composed::signed_key::SignedPublicKey,
+    signature: &::pgp::packet::Signature,
+    mut data: R,
+) -> Result<(), RPMError> {
+    if let Some(key_id) = signature.issuer() {
+        log::trace!("Signature has issuer ref: {:?}", key_id);
+
+        if public_key.key_id() == *key_id {
+            return Verifier::verify_with_key(
+                signature,
+                public_key,
+                public_key.primary_key.public_params(),
+                data,
+            );
+        } else {
+            log::trace!(
+                "Signature issuer key id {:?} does not match primary keys key id: {:?}",
+                key_id,
+                public_key.key_id()
+            );
+        }
+
+        public_key
+            .public_subkeys
+            .iter()
+            .filter(|sub_key| {
+                if sub_key.key_id().as_ref() == key_id.as_ref() {
+                    log::trace!(
+                        "Found a matching key id {:?} == {:?}",
+                        sub_key.key_id(),
+                        key_id
+                    );
+                    true
+                } else {
+                    log::trace!("Not the one we want: {:?}", sub_key);
+                    false
+                }
+            })
+            .try_fold(
+                Err(RPMError::KeyNotFoundError {
+                    key_ref: format!("{:?}", key_id),
+                }),
+                |previous_res, sub_key| {
+                    if previous_res.is_err() {
+                        log::trace!("Test next candidate subkey");
+                        Ok(Verifier::verify_with_key(
+                            signature,
+                            sub_key,
+                            sub_key.key.public_params(),
+                            &mut data,
+                        ))
+                    } else {
+                        log::trace!("Signature already verified, nop");
+                        // short circuit, no need to look at further candidates
+                        Err(Ok(()))
+                    }
+                },
+            )
+            .unwrap_or_else(|done| done)
+    } else {
+        log::trace!(
+            "Signature has no issuer ref, attempting primary key: {:?}",
+            public_key.primary_key.key_id()
+        );
+        Verifier::verify_with_key(
+            signature,
+            public_key,
+            public_key.primary_key.public_params(),
+            data,
+        )
+    }
+}
+
+/// Whether the primary key or one of the subkeys of `public_key` has the id `key_id`.
+fn has_key_id(
+    public_key: &::pgp::composed::signed_key::SignedPublicKey,
+    key_id: &::pgp::types::KeyId,
+) -> bool {
+    public_key.key_id() == *key_id
+        || public_key
+            .public_subkeys
+            .iter()
+            .any(|sub_key| sub_key.key_id() == *key_id)
+}
+
+/// Describe a parsed signature packet, short of the primary key which takes a verifier.
+fn describe(signature: &::pgp::packet::Signature) -> super::SignatureInfo {
+    use num_traits::FromPrimitive;
+
+    let fingerprint = signature
+        .config
+        .subpackets()
+        .find_map(|subpacket| match subpacket {
+            Subpacket::IssuerFingerprint(_, fingerprint) => Some(hex::encode(fingerprint)),
+            _ => None,
+        });
+    super::SignatureInfo {
+        algorithm: algorithm_type(signature.config.pub_alg),
+        hash_algorithm: crate::FileDigestAlgorithm::from_u8(signature.config.hash_alg as u8),
+        key_id: signature
+            .issuer()
+            .map(|key_id| hex::encode(key_id.as_ref())),
+        fingerprint,
+        primary_key_id: None,
+        created: signature.created().copied(),
+    }
+}
+
+/// Describe an OpenPGP signature packet, without a key.
+pub(crate) fn parse_signature_info(signature: &[u8]) -> Result<super::SignatureInfo, RPMError> {
+    Ok(describe(&Verifier::parse_signature(signature)?))
+}
+
+/// Describe a signature, `public_keys` are searched for the key that issued it.
+fn signature_info<'k>(
+    signature: &[u8],
+    public_keys: impl IntoIterator<Item = &'k ::pgp::composed::signed_key::SignedPublicKey>,
+) -> super::SignatureInfo {
+    let signature = match Verifier::parse_signature(signature) {
+        Ok(signature) => signature,
+        Err(_) => return super::SignatureInfo::default(),
+    };
+    let primary_key_id = signature.issuer().and_then(|key_id| {
+        public_keys
+            .into_iter()
+            .find(|public_key| has_key_id(public_key, key_id))
+            .map(|public_key| hex::encode(public_key.key_id().as_ref()))
+    });
+    super::SignatureInfo {
+        primary_key_id,
+        ..describe(&signature)
+    }
+}
+
+impl traits::Verifying for Verifier {
     type Signature = Vec<u8>;
     /// Despite the fact the API suggest zero copy pattern,
     /// it internally creates a copy until crate `pgp` provides
     /// a `Read` based implementation.
-    fn verify<R: Read>(&self, mut data: R, signature: &[u8]) -> Result<(), RPMError> {
+    fn verify<R: Read>(&self, data: R, signature: &[u8]) -> Result<(), RPMError> {
         let signature = Self::parse_signature(signature)?;
 
         log::debug!("Signature issued by: {:?}", signature.issuer());
 
-        if let Some(key_id) = signature.issuer() {
-            log::trace!("Signature has issuer ref: {:?}", key_id);
-
-            if self.public_key.key_id() == *key_id {
-                return signature.verify(&self.public_key, data).map_err(|e| {
-                    RPMError::VerificationError {
-                        source: Box::new(e),
-                        key_ref: format!("{:?}", key_id),
-                    }
-                });
-            } else {
-                log::trace!(
-                    "Signature issuer key id {:?} does not match primary keys key id: {:?}",
-                    key_id,
-                    self.public_key.key_id()
-                );
-            }
+        verify_with_public_key(&self.public_key, &signature, data)
+    }
 
-            self.public_key
-                .public_subkeys
-                .iter()
-                .filter(|sub_key| {
-                    if sub_key.key_id().as_ref() == key_id.as_ref() {
-                        log::trace!(
-                            "Found a matching key id {:?} == {:?}",
-                            sub_key.key_id(),
-                            key_id
-                        );
-                        true
-                    } else {
-                        log::trace!("Not the one we want: {:?}", sub_key);
-                        false
-                    }
-                })
-                .fold(
-                    Err(RPMError::KeyNotFoundError {
-                        key_ref: format!("{:?}", key_id),
-                    }),
-                    |previous_res, sub_key| {
-                        if previous_res.is_err() {
-                            log::trace!("Test next candidate subkey");
-                            signature.verify(sub_key, &mut data).map_err(|e| {
-                                RPMError::VerificationError {
-                                    source: Box::new(e),
-                                    key_ref: format!("{:?}", sub_key.key_id()),
-                                }
-                            })
-                        } else {
-                            log::trace!("Signature already verified, nop");
-                            Ok(())
-                        }
-                    },
-                )
-        } else {
-            log::trace!(
-                "Signature has no issuer ref, attempting primary key: {:?}",
-                self.public_key.primary_key.key_id()
-            );
-            signature
-                .verify(&self.public_key, data)
-                .map_err(|e| RPMError::VerificationError {
-                    source: Box::new(e),
-                    key_ref: format!("{:?}", self.public_key.key_id()),
-                })
-        }
+    fn signature_info(&self, signature: &[u8]) -> super::SignatureInfo {
+        signature_info(signature, std::iter::once(&self.public_key))
     }
 }
 
@@ -208,10 +699,175 @@ impl Verifier {
     }
 }
 
+/// Verifier implementation using the `pgp` crate, trusting any number of keys.
+///
+/// Keys are accepted ascii armored as well as in binary form, i.e. the
+/// contents of `/etc/pki/rpm-gpg`. A signature is verified with the key
+/// that has a primary key or subkey matching the issuer of the signature.
+#[derive(Clone, Debug, Default)]
+pub struct Keyring {
+    public_keys: Vec<::pgp::composed::signed_key::SignedPublicKey>,
+}
+
+impl Keyring {
+    const ARMOR_BEGIN: &'static str = "-----BEGIN PGP PUBLIC KEY BLOCK-----";
+    const ARMOR_END: &'static str = "-----END PGP PUBLIC KEY BLOCK-----";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load all keys of an ascii armored or binary key file.
+    pub fn load_from_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        let mut keyring = Self::new();
+        keyring.add_keys_from_bytes(input)?;
+        Ok(keyring)
+    }
+
+    /// Load all key files within a directory, i.e. `/etc/pki/rpm-gpg`.
+    ///
+    /// Subdirectories are ignored.
+    pub fn load_from_dir<P: AsRef<std::path::Path>>(path: P) -> Result<Self, RPMError> {
+        let mut paths = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+
+        let mut keyring = Self::new();
+        for path in paths.iter().filter(|path| path.is_file()) {
+            log::debug!("Loading keys from {}", path.display());
+            keyring.add_keys_from_bytes(&std::fs::read(path)?)?;
+        }
+        Ok(keyring)
+    }
+
+    /// Add all keys of an ascii armored or binary key file,
+    /// armored files may contain multiple key blocks.
+    pub fn add_keys_from_bytes(&mut self, input: &[u8]) -> Result<(), RPMError> {
+        let key_load_error = |e: ::pgp::errors::Error, details| RPMError::KeyLoadError {
+            source: Box::new(e),
+            details,
+        };
+
+        let mut public_keys = Vec::new();
+        match ::std::str::from_utf8(input) {
+            Ok(text) if text.trim_start().starts_with(Self::ARMOR_BEGIN) => {
+                for block in text.split(Self::ARMOR_END) {
+                    let block = match block.find(Self::ARMOR_BEGIN) {
+                        Some(start) => &block[start..],
+                        None => continue,
+                    };
+                    let block = format!("{}{}\n", block, Self::ARMOR_END);
+                    let (keys, _) =
+                        ::pgp::composed::signed_key::SignedPublicKey::from_string_many(&block)
+                            .map_err(|e| {
+                                key_load_error(e, "Failed to parse bytes as ascii armored key")
+                            })?;
+                    for key in keys {
+                        public_keys.push(key.map_err(|e| {
+                            key_load_error(e, "Failed to parse bytes as ascii armored key")
+                        })?);
+                    }
+                }
+            }
+            _ => {
+                for key in ::pgp::composed::signed_key::SignedPublicKey::from_bytes_many(
+                    Cursor::new(input),
+                ) {
+                    public_keys.push(
+                        key.map_err(|e| key_load_error(e, "Failed to parse bytes as binary key"))?,
+                    );
+                }
+            }
+        }
+
+        if public_keys.is_empty() {
+            return Err(RPMError::KeyLoadError {
+                source: "no public key found".into(),
+                details: "Failed to find a key in the given bytes",
+            });
+        }
+        self.public_keys.extend(public_keys);
+        Ok(())
+    }
+
+    /// The ids of the primary keys as lowercase hex.
+    pub fn key_ids(&self) -> Vec<String> {
+        self.public_keys
+            .iter()
+            .map(|public_key| hex::encode(public_key.key_id().as_ref()))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.public_keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.public_keys.is_empty()
+    }
+
+    /// Verify a signature and return the id of the primary key that verified it.
+    pub fn verify_with_key_id<R: Read>(
+        &self,
+        mut data: R,
+        signature: &[u8],
+    ) -> Result<String, RPMError> {
+        let signature = Verifier::parse_signature(signature)?;
+
+        log::debug!("Signature issued by: {:?}", signature.issuer());
+
+        // every candidate key consumes the data
+        let mut buffer = Vec::new();
+        data.read_to_end(&mut buffer)?;
+
+        let mut result = Err(RPMError::KeyNotFoundError {
+            key_ref: format!("{:?}", signature.issuer()),
+        });
+        for public_key in self
+            .public_keys
+            .iter()
+            .filter(|public_key| match signature.issuer() {
+                Some(key_id) => has_key_id(public_key, key_id),
+                None => true,
+            })
+        {
+            result = verify_with_public_key(public_key, &signature, buffer.as_slice())
+                .map(|()| hex::encode(public_key.key_id().as_ref()));
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+}
+
+impl From<Verifier> for Keyring {
+    fn from(verifier: Verifier) -> Self {
+        Self {
+            public_keys: vec![verifier.public_key],
+        }
+    }
+}
+
+impl traits::Verifying for Keyring {
+    type Signature = Vec<u8>;
+
+    fn verify<R: Read>(&self, data: R, signature: &[u8]) -> Result<(), RPMError> {
+        let key_id = self.verify_with_key_id(data, signature)?;
+        log::debug!("Signature verified by key: {}", key_id);
+        Ok(())
+    }
+
+    fn signature_info(&self, signature: &[u8]) -> super::SignatureInfo {
+        signature_info(signature, &self.public_keys)
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod test {
 
-    use super::super::{echo_signature, Signing, Verifying};
+    use super::super::{algorithm::AlgorithmType, echo_signature, Signing, Verifying};
     use super::*;
 
     use super::Signer;
@@ -234,6 +890,20 @@ pub(crate) mod test {
         (signing_key.to_vec(), verification_key.to_vec())
     }
 
+    /// Load an Ed25519 and an ECDSA NIST P-256 key pair, in that order.
+    pub(crate) fn load_ecc_asc_keys() -> [(Vec<u8>, Vec<u8>); 2] {
+        [
+            (
+                include_bytes!("../../../test_assets/ed25519_secret_key.asc").to_vec(),
+                include_bytes!("../../../test_assets/ed25519_public_key.asc").to_vec(),
+            ),
+            (
+                include_bytes!("../../../test_assets/ecdsa_p256_secret_key.asc").to_vec(),
+                include_bytes!("../../../test_assets/ecdsa_p256_public_key.asc").to_vec(),
+            ),
+        ]
+    }
+
     #[test]
     fn parse_asc() {
         // assert `prep()` itself is sane
@@ -267,6 +937,216 @@ pub(crate) mod test {
             .expect("failed to verify just signed signature");
     }
 
+    #[test]
+    fn sign_verify_roundtrip_ecc() {
+        let _ = env_logger::try_init();
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let expected = [AlgorithmType::EdDSA, AlgorithmType::ECDSA];
+        for ((signing_key, verification_key), algorithm) in
+            load_ecc_asc_keys().iter().zip(expected.iter())
+        {
+            let signer = Signer::load_from_asc_bytes(signing_key).expect("PK parsing failed");
+            let verifier =
+                Verifier::load_from_asc_bytes(verification_key).expect("PK parsing failed");
+            assert_eq!(*algorithm, signer.algorithm());
+
+            let signature = signer.sign(&data[..]).expect("signed");
+            verifier
+                .verify(&data[..], signature.as_slice())
+                .expect("failed to verify just signed signature");
+            assert!(verifier
+                .verify(&b"other data"[..], signature.as_slice())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn verify_gpg_ecc_signatures() {
+        let _ = env_logger::try_init();
+        let data = include_bytes!("../../../test_assets/awesome.toml");
+        let signatures = [
+            &include_bytes!("../../../test_assets/awesome.toml.ed25519.sig")[..],
+            &include_bytes!("../../../test_assets/awesome.toml.ecdsa_p256.sig")[..],
+        ];
+        for ((_, verification_key), signature) in load_ecc_asc_keys().iter().zip(signatures.iter())
+        {
+            let verifier =
+                Verifier::load_from_asc_bytes(verification_key).expect("PK parsing failed");
+            verifier
+                .verify(&data[..], signature)
+                .expect("gpg signature should verify");
+        }
+    }
+
+    #[test]
+    fn sign_with_passphrase() {
+        let _ = env_logger::try_init();
+        let signing_key = include_bytes!("../../../test_assets/ed25519_protected_secret_key.asc");
+        let verification_key =
+            include_bytes!("../../../test_assets/ed25519_protected_public_key.asc");
+        let signer = Signer::load_from_asc_bytes(signing_key).expect("PK parsing failed");
+        let verifier = Verifier::load_from_asc_bytes(verification_key).expect("PK parsing failed");
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+
+        assert!(matches!(
+            signer.sign(&data[..]),
+            Err(RPMError::WrongPassphrase)
+        ));
+        assert!(matches!(
+            signer.clone().with_passphrase("wrong").sign(&data[..]),
+            Err(RPMError::WrongPassphrase)
+        ));
+
+        let signature = signer
+            .clone()
+            .with_passphrase("fez test passphrase")
+            .sign(&data[..])
+            .expect("signed");
+        verifier
+            .verify(&data[..], signature.as_slice())
+            .expect("failed to verify just signed signature");
+
+        let signature = signer
+            .with_passphrase_fn(|| "fez test passphrase".to_string())
+            .sign(&data[..])
+            .expect("signed");
+        verifier
+            .verify(&data[..], signature.as_slice())
+            .expect("failed to verify just signed signature");
+    }
+
+    #[test]
+    fn sign_with_passphrase_checksum() {
+        let _ = env_logger::try_init();
+        // protected with S2K usage 255, i.e. a two octet checksum instead of a SHA-1 hash
+        let signing_key =
+            include_bytes!("../../../test_assets/ed25519_checksum_protected_secret_key.asc");
+        let verification_key = include_bytes!("../../../test_assets/ed25519_public_key.asc");
+        let signer = Signer::load_from_asc_bytes(signing_key).expect("PK parsing failed");
+        let verifier = Verifier::load_from_asc_bytes(verification_key).expect("PK parsing failed");
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+
+        for passphrase in ["", "wrong", "fez test passphrase "] {
+            assert!(matches!(
+                signer.clone().with_passphrase(passphrase).sign(&data[..]),
+                Err(RPMError::WrongPassphrase)
+            ));
+        }
+
+        let signature = signer
+            .with_passphrase("fez test passphrase")
+            .sign(&data[..])
+            .expect("signed");
+        verifier
+            .verify(&data[..], signature.as_slice())
+            .expect("failed to verify just signed signature");
+
+        // the last encrypted octet flipped, which only breaks the checksum
+        let signing_key =
+            include_bytes!("../../../test_assets/ed25519_bad_checksum_secret_key.asc");
+        let signer = Signer::load_from_asc_bytes(signing_key).expect("PK parsing failed");
+        assert!(matches!(
+            signer
+                .with_passphrase("fez test passphrase")
+                .sign(&data[..]),
+            Err(RPMError::WrongPassphrase)
+        ));
+
+        // other forms of S2K usage 255 are refused instead of rewritten
+        let armored = std::str::from_utf8(include_bytes!(
+            "../../../test_assets/ed25519_checksum_protected_secret_key.asc"
+        ))
+        .unwrap();
+        let mut packets = Vec::new();
+        ::pgp::armor::Dearmor::new(std::io::Cursor::new(armored.as_bytes()))
+            .read_to_end(&mut packets)
+            .unwrap();
+        let mut simple_s2k = packets.clone();
+        let usage = 2 + super::public_key_len(&simple_s2k[2..]).unwrap();
+        assert_eq!(255, simple_s2k[usage]);
+        simple_s2k[usage + 2] = 0;
+        assert!(matches!(
+            super::rewrite_checksum_protection(&mut simple_s2k),
+            Err(RPMError::UnsupportedKeyProtection(_))
+        ));
+        assert!(super::rewrite_checksum_protection(&mut packets).expect("rewritten"));
+        assert_eq!(254, packets[usage]);
+    }
+
+    #[test]
+    fn keyring_verify() {
+        use ::pgp::ser::Serialize;
+
+        let (signer, verifier) = prep();
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]).expect("signed");
+
+        // a single armored file with multiple key blocks
+        let [(_, ed25519_key), (_, ecdsa_key)] = load_ecc_asc_keys();
+        let armored = [ed25519_key, ecdsa_key].concat();
+        let mut keyring = Keyring::load_from_bytes(&armored).expect("PK parsing failed");
+        assert_eq!(2, keyring.len());
+        assert!(matches!(
+            keyring.verify_with_key_id(&data[..], &signature),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+        assert_eq!(None, keyring.signature_info(&signature).primary_key_id);
+
+        let binary = verifier.public_key.to_bytes().expect("serializable");
+        keyring
+            .add_keys_from_bytes(&binary)
+            .expect("PK parsing failed");
+        assert_eq!(3, keyring.len());
+
+        let key_id = hex::encode(verifier.public_key.key_id().as_ref());
+        assert_eq!(key_id, keyring.key_ids()[2]);
+        assert_eq!(
+            key_id,
+            keyring
+                .verify_with_key_id(&data[..], &signature)
+                .expect("failed to verify just signed signature")
+        );
+        assert_eq!(
+            Some(key_id),
+            keyring.signature_info(&signature).primary_key_id
+        );
+        assert!(keyring
+            .verify_with_key_id(&b"other data"[..], &signature)
+            .is_err());
+
+        assert!(Keyring::load_from_bytes(b"no key").is_err());
+    }
+
+    #[test]
+    fn keyring_load_from_dir() {
+        use ::pgp::ser::Serialize;
+
+        let (signer, verifier) = prep();
+        let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join("keyring");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("subdir")).expect("create key dir");
+        for (i, (_, verification_key)) in load_ecc_asc_keys().iter().enumerate() {
+            std::fs::write(dir.join(format!("RPM-GPG-KEY-{}", i)), verification_key)
+                .expect("write key");
+        }
+        std::fs::write(
+            dir.join("RPM-GPG-KEY-binary.gpg"),
+            verifier.public_key.to_bytes().expect("serializable"),
+        )
+        .expect("write key");
+
+        let keyring = Keyring::load_from_dir(&dir).expect("PK parsing failed");
+        assert_eq!(3, keyring.len());
+
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]).expect("signed");
+        keyring
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+    }
+
     #[test]
     fn verify_pgp_crate() {
         use ::pgp::types::{PublicKeyTrait, SecretKeyTrait};
@@ -0,0 +1,168 @@
+//! Outcome of verifying all signatures and digests of a package.
+
+use super::algorithm::AlgorithmType;
+use crate::constants::IndexSignatureTag;
+use crate::errors::RPMError;
+use crate::FileDigestAlgorithm;
+
+/// What a signature or a digest was calculated over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignedRange {
+    /// The header only, the payload is covered by the payload digest within the header.
+    Header,
+    /// The header followed by the payload.
+    HeaderAndPayload,
+    /// The compressed payload, as recorded in `RPMTAG_PAYLOADDIGEST`.
+    Payload,
+}
+
+/// The signature tags of the signature header and what the signatures stored in them span.
+pub(crate) const SIGNATURE_TAGS: [(IndexSignatureTag, SignedRange); 4] = [
+    (IndexSignatureTag::RPMSIGTAG_RSA, SignedRange::Header),
+    (IndexSignatureTag::RPMSIGTAG_DSA, SignedRange::Header),
+    (
+        IndexSignatureTag::RPMSIGTAG_PGP,
+        SignedRange::HeaderAndPayload,
+    ),
+    (
+        IndexSignatureTag::RPMSIGTAG_GPG,
+        SignedRange::HeaderAndPayload,
+    ),
+];
+
+/// Metadata of a signature, as far as the verifier understands its format.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SignatureInfo {
+    /// The public key algorithm the signature was made with.
+    pub algorithm: Option<AlgorithmType>,
+    /// The hash algorithm the signed data was digested with.
+    pub hash_algorithm: Option<FileDigestAlgorithm>,
+    /// The id of the issuing key as lowercase hex, i.e. `24c6a8a7f4a80eb5`.
+    pub key_id: Option<String>,
+    /// The fingerprint of the issuing key as lowercase hex, if the signature
+    /// records it, which version 3 signatures never do.
+    pub fingerprint: Option<String>,
+    /// The id of the primary key of the verifier the issuing key belongs to,
+    /// differs from `key_id` for signatures made with a subkey.
+    pub primary_key_id: Option<String>,
+    /// The time the signature was created.
+    pub created: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A signature found in the signature header, described without verifying it.
+///
+/// Obtained with [`Header::get_signatures`](crate::Header::get_signatures).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSignature {
+    /// The tag the signature is stored in.
+    pub tag: IndexSignatureTag,
+    /// What the signature spans.
+    pub range: SignedRange,
+    /// Algorithm, issuer and creation time of the signature.
+    pub info: SignatureInfo,
+}
+
+/// The result of checking a single signature or digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckOutcome {
+    /// The signature or digest matches.
+    Valid,
+    /// None of the keys of the verifier issued the signature, never the case for digests.
+    KeyNotFound,
+    /// The signature or digest does not match or could not be checked.
+    Invalid(String),
+}
+
+impl CheckOutcome {
+    /// Classify the result of [`Verifying::verify`](super::Verifying::verify).
+    pub(crate) fn from_result(result: Result<(), RPMError>) -> Self {
+        match result {
+            Ok(()) => Self::Valid,
+            Err(RPMError::KeyNotFoundError { .. }) => Self::KeyNotFound,
+            Err(e) => Self::Invalid(e.to_string()),
+        }
+    }
+}
+
+/// A signature found in the signature header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureCheck {
+    /// The tag the signature is stored in.
+    pub tag: IndexSignatureTag,
+    /// What the signature spans.
+    pub range: SignedRange,
+    /// Algorithm, issuer and creation time of the signature.
+    pub info: SignatureInfo,
+    pub outcome: CheckOutcome,
+}
+
+impl SignatureCheck {
+    /// Whether the verifier has the key that issued the signature.
+    pub fn key_found(&self) -> bool {
+        self.outcome != CheckOutcome::KeyNotFound
+    }
+}
+
+/// A digest found in the signature header, or the payload digest of the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestCheck {
+    /// What the digest spans.
+    pub range: SignedRange,
+    pub algorithm: FileDigestAlgorithm,
+    pub outcome: CheckOutcome,
+}
+
+/// Overall classification of a [`VerificationReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerificationStatus {
+    /// All signatures and digests match.
+    Verified,
+    /// The package carries no signature, the digests match.
+    Unsigned,
+    /// No signature is invalid, but at least one was issued by a key the verifier does not know.
+    UnknownKey,
+    /// At least one signature or digest does not match.
+    BadSignature,
+}
+
+/// Every signature and digest of a package along with the outcome of checking it.
+///
+/// Obtained with [`RPMPackage::verification_report`](crate::RPMPackage::verification_report).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VerificationReport {
+    pub signatures: Vec<SignatureCheck>,
+    pub digests: Vec<DigestCheck>,
+}
+
+impl VerificationReport {
+    /// Classify the report, a single mismatch makes the whole package bad.
+    pub fn status(&self) -> VerificationStatus {
+        let outcomes = || {
+            self.signatures
+                .iter()
+                .map(|check| &check.outcome)
+                .chain(self.digests.iter().map(|check| &check.outcome))
+        };
+        if outcomes().any(|outcome| matches!(outcome, CheckOutcome::Invalid(_))) {
+            VerificationStatus::BadSignature
+        } else if self.signatures.is_empty() {
+            VerificationStatus::Unsigned
+        } else if outcomes().any(|outcome| *outcome == CheckOutcome::KeyNotFound) {
+            VerificationStatus::UnknownKey
+        } else {
+            VerificationStatus::Verified
+        }
+    }
+
+    /// The key ids of the signatures that were verified.
+    pub fn verified_key_ids(&self) -> Vec<&str> {
+        let mut key_ids = self
+            .signatures
+            .iter()
+            .filter(|check| check.outcome == CheckOutcome::Valid)
+            .filter_map(|check| check.info.key_id.as_deref())
+            .collect::<Vec<_>>();
+        key_ids.dedup();
+        key_ids
+    }
+}
@@ -3,68 +3,100 @@
 //! Does not contain hashing! Hashes are fixed by the rpm
 //! "spec" to sha1, md5 (yes, that is correct), sha2_256.
 
+use super::SignatureInfo;
 #[allow(unused)]
 use crate::errors::*;
 use std::fmt::Debug;
 use std::io::Read;
 
 pub mod algorithm {
-
-    pub trait Algorithm: super::Debug {}
-    /// currently only RSA is required
+    /// The public key algorithm of a signature, as determined at runtime.
     ///
-    /// Farsight for future algorithm extensions of rpm
-    /// without breaking the API
-    #[derive(Debug, Clone, Copy)]
-    #[allow(non_camel_case_types)]
-
-    pub struct RSA;
-
-    impl Algorithm for RSA {}
+    /// rpm stores RSA signatures in `RPMSIGTAG_RSA`/`RPMSIGTAG_PGP`
+    /// and all others in `RPMSIGTAG_DSA`/`RPMSIGTAG_GPG`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum AlgorithmType {
+        RSA,
+        EdDSA,
+        ECDSA,
+    }
 }
 
 /// Signing trait to be implement for RPM signing.
-pub trait Signing<A>: Debug
+pub trait Signing: Debug
 where
-    A: algorithm::Algorithm,
     Self::Signature: AsRef<[u8]>,
 {
     type Signature;
     fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError>;
+
+    /// The algorithm of the produced signatures, which decides the signature tags.
+    fn algorithm(&self) -> algorithm::AlgorithmType;
 }
 
-impl<A, T, S> Signing<A> for &T
+impl<T, S> Signing for &T
 where
-    T: Signing<A, Signature = S>,
-    A: algorithm::Algorithm,
+    T: Signing<Signature = S>,
     S: AsRef<[u8]>,
 {
     type Signature = S;
     fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
         T::sign::<R>(self, data)
     }
+
+    fn algorithm(&self) -> algorithm::AlgorithmType {
+        T::algorithm(self)
+    }
+}
+
+/// Which parts of a package a signature spans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureMode {
+    /// A signature over the header and one over header and payload,
+    /// next to the legacy MD5 and SHA-1 digests.
+    HeaderAndPayload,
+    /// Only a signature over the header, as rpmsign does since rpm 4.16.
+    ///
+    /// The payload is covered by the SHA-256 `RPMTAG_PAYLOADDIGEST` within the signed header,
+    /// the signature header carries a SHA-256 header digest but no MD5 or SHA-1 digest.
+    HeaderOnly,
+}
+
+impl Default for SignatureMode {
+    fn default() -> Self {
+        Self::HeaderAndPayload
+    }
 }
 
 /// Verification trait to be implement for RPM signature verification.
-pub trait Verifying<A>: Debug
+pub trait Verifying: Debug
 where
-    A: algorithm::Algorithm,
     Self::Signature: AsRef<[u8]>,
 {
     type Signature;
     fn verify<R: Read>(&self, data: R, signature: &[u8]) -> Result<(), RPMError>;
+
+    /// Algorithm, issuer and creation time of a signature without verifying it.
+    ///
+    /// Defaults to an empty description, for verifiers that do not understand the format.
+    fn signature_info(&self, _signature: &[u8]) -> SignatureInfo {
+        SignatureInfo::default()
+    }
 }
 
-impl<A, T, S> Verifying<A> for &T
+impl<T, S> Verifying for &T
 where
-    T: Verifying<A, Signature = S>,
-    A: algorithm::Algorithm,
+    T: Verifying<Signature = S>,
     S: AsRef<[u8]>,
 {
     type Signature = S;
     fn verify<R: Read>(&self, data: R, signature: &[u8]) -> Result<(), RPMError> {
         T::verify::<R>(self, data, signature)
     }
+
+    fn signature_info(&self, signature: &[u8]) -> SignatureInfo {
+        T::signature_info(self, signature)
+    }
 }
 
 pub mod key {
@@ -86,21 +118,19 @@ pub mod key {
 }
 
 /// Implement unreachable signer for empty tuple `()`
-impl<A> Signing<A> for std::marker::PhantomData<A>
-where
-    A: algorithm::Algorithm,
-{
+impl<T> Signing for std::marker::PhantomData<T> {
     type Signature = Vec<u8>;
     fn sign<R: Read>(&self, _data: R) -> Result<Self::Signature, RPMError> {
         unreachable!("if you want to verify, you need to implement `sign` of the `Signing` trait")
     }
+
+    fn algorithm(&self) -> algorithm::AlgorithmType {
+        unreachable!("if you want to verify, you need to implement `sign` of the `Signing` trait")
+    }
 }
 
 /// Implement unreachable verifier for the empty tuple`()`
-impl<A> Verifying<A> for std::marker::PhantomData<A>
-where
-    A: algorithm::Algorithm,
-{
+impl<T> Verifying for std::marker::PhantomData<T> {
     type Signature = Vec<u8>;
     fn verify<R: Read>(&self, _data: R, _x: &[u8]) -> Result<(), RPMError> {
         unreachable!(
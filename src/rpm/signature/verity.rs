@@ -0,0 +1,374 @@
+//! fs-verity file signatures, which the kernel checks when fs-verity
+//! is enabled on a file, with rpm 4.17 or later and its fsverity plugin.
+//!
+//! The fs-verity digest of a file is the SHA-256 digest of a descriptor
+//! holding the file size and the root hash of a Merkle tree over its 4 KiB blocks.
+//! A signature is a detached PKCS#7 signature of that digest, prefixed with
+//! `FSVerity` and the algorithm, as made by libfsverity:
+//! without certificates and authenticated attributes, the signer identified
+//! by issuer and serial number of its certificate.
+//!
+//! rpm stores them base64 encoded in `RPMSIGTAG_VERITYSIGNATURES`.
+
+use super::x509::{pem_str, Certificate, SigningKey, VerifyingKey};
+use crate::constants::FS_VERITY_HASH_ALG_SHA256;
+use crate::errors::*;
+use crate::FileDigestAlgorithm;
+
+use std::io::Read;
+
+const FS_VERITY_BLOCK_SIZE: usize = 4096;
+const FS_VERITY_LOG_BLOCK_SIZE: u8 = 12;
+const SHA256_LEN: usize = 32;
+
+// DER encoded object identifiers
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+const OID_PKCS7_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x01];
+const OID_PKCS7_SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+
+// DER tags
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const CONTEXT_0: u8 = 0xA0;
+const CONTEXT_1: u8 = 0xA1;
+
+/// Calculate the fs-verity digest of a file's content.
+pub fn digest(content: &[u8]) -> Result<Vec<u8>, RPMError> {
+    digest_reader(content)
+}
+
+/// Calculate the fs-verity digest of a file's content read from `input`.
+pub fn digest_reader<R: Read>(mut input: R) -> Result<Vec<u8>, RPMError> {
+    let mut data_size = 0u64;
+    let mut level = Vec::new();
+    let mut block = vec![0u8; FS_VERITY_BLOCK_SIZE];
+    loop {
+        let mut len = 0;
+        while len < block.len() {
+            match input.read(&mut block[len..])? {
+                0 => break,
+                n => len += n,
+            }
+        }
+        if len == 0 {
+            break;
+        }
+        data_size += len as u64;
+        block[len..].fill(0);
+        level.extend(FileDigestAlgorithm::Sha2_256.digest(&block)?);
+        if len < block.len() {
+            break;
+        }
+    }
+
+    // hash the blocks of each level until a single hash remains, the root hash
+    while level.len() > SHA256_LEN {
+        let mut next = Vec::with_capacity(level.len() / FS_VERITY_BLOCK_SIZE * SHA256_LEN);
+        for hashes in level.chunks(FS_VERITY_BLOCK_SIZE) {
+            block[..hashes.len()].copy_from_slice(hashes);
+            block[hashes.len()..].fill(0);
+            next.extend(FileDigestAlgorithm::Sha2_256.digest(&block)?);
+        }
+        level = next;
+    }
+    // empty files have an all zero root hash
+    level.resize(SHA256_LEN, 0);
+
+    // struct fsverity_descriptor
+    let mut descriptor = Vec::with_capacity(256);
+    descriptor.extend_from_slice(&[
+        1, // version
+        FS_VERITY_HASH_ALG_SHA256 as u8,
+        FS_VERITY_LOG_BLOCK_SIZE,
+        0, // salt size
+    ]);
+    descriptor.extend_from_slice(&0u32.to_le_bytes()); // signature size, zero within the digest
+    descriptor.extend_from_slice(&data_size.to_le_bytes());
+    descriptor.extend_from_slice(&level);
+    descriptor.resize(256, 0); // the root hash has room for 64 bytes, followed by salt and reserved space
+    FileDigestAlgorithm::Sha2_256.digest(&descriptor)
+}
+
+/// The signed content, `struct fsverity_formatted_digest`.
+fn formatted_digest(digest: &[u8]) -> Result<Vec<u8>, RPMError> {
+    if digest.len() != SHA256_LEN {
+        return Err(RPMError::InvalidFileDigestLength {
+            algorithm: FileDigestAlgorithm::Sha2_256,
+            expected: SHA256_LEN,
+            actual: digest.len(),
+        });
+    }
+    let mut formatted = b"FSVerity".to_vec();
+    formatted.extend_from_slice(&(FS_VERITY_HASH_ALG_SHA256 as u16).to_le_bytes());
+    formatted.extend_from_slice(&(SHA256_LEN as u16).to_le_bytes());
+    formatted.extend_from_slice(digest);
+    Ok(formatted)
+}
+
+/// Encode a DER element.
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Decode the DER element of `tag` at the start of `input`, returning its content and the rest.
+fn der_read(input: &[u8], tag: u8) -> Result<(&[u8], &[u8]), RPMError> {
+    const INVALID: RPMError = RPMError::InvalidVeritySignature("invalid DER encoding");
+    if input.len() < 2 {
+        return Err(INVALID);
+    }
+    if input[0] != tag {
+        return Err(RPMError::InvalidVeritySignature(
+            "unexpected element in PKCS#7 signature",
+        ));
+    }
+    let (len, header_len) = match input[1] {
+        len if len < 0x80 => (len as usize, 2),
+        0x81..=0x84 => {
+            let count = (input[1] & 0x7F) as usize;
+            let bytes = input.get(2..2 + count).ok_or(INVALID)?;
+            let len = bytes.iter().fold(0usize, |len, b| len << 8 | *b as usize);
+            (len, 2 + count)
+        }
+        _ => return Err(INVALID),
+    };
+    let content = input.get(header_len..header_len + len).ok_or(INVALID)?;
+    Ok((content, &input[header_len + len..]))
+}
+
+/// An `AlgorithmIdentifier` of `oid`, with `NULL` parameters for the ones which have them.
+fn algorithm_identifier(oid: &[u8], null_parameters: bool) -> Vec<u8> {
+    let mut content = der(OBJECT_IDENTIFIER, oid);
+    if null_parameters {
+        content.extend(der(NULL, &[]));
+    }
+    der(SEQUENCE, &content)
+}
+
+/// The `IssuerAndSerialNumber` of a certificate.
+fn issuer_and_serial(certificate: &Certificate) -> Vec<u8> {
+    let mut content = certificate.issuer.clone();
+    content.extend(der(INTEGER, &certificate.serial));
+    der(SEQUENCE, &content)
+}
+
+/// Signs fs-verity file digests with a private key and its X.509 certificate.
+///
+/// Used with [`RPMBuilder::sign_files_verity`](crate::RPMBuilder::sign_files_verity) to add
+/// `RPMSIGTAG_VERITYSIGNATURES` to built packages.
+#[derive(Debug)]
+pub struct VeritySigner {
+    key: SigningKey,
+    issuer_and_serial: Vec<u8>,
+}
+
+impl VeritySigner {
+    /// Load a PEM encoded private key, PKCS#8, PKCS#1 for RSA or SEC1 for ECDSA keys,
+    /// together with its PEM or DER encoded certificate, as loaded into the kernel's
+    /// `.fs-verity` keyring.
+    pub fn load_from_pem(key: &[u8], certificate: &[u8]) -> Result<Self, RPMError> {
+        let key = SigningKey::load_from_pem(pem_str(key)?)?;
+        let certificate = Certificate::load(certificate)?;
+        if key.subject_public_key()? != certificate.subject_public_key {
+            return Err(RPMError::KeyLoadError {
+                source: "the public key of the certificate differs".into(),
+                details: "the certificate does not belong to the private key",
+            });
+        }
+        Ok(Self {
+            key,
+            issuer_and_serial: issuer_and_serial(&certificate),
+        })
+    }
+
+    /// Sign an fs-verity file digest, returning the DER encoded PKCS#7 signature.
+    pub fn sign_digest(&self, digest: &[u8]) -> Result<Vec<u8>, RPMError> {
+        let signed = FileDigestAlgorithm::Sha2_256.digest(&formatted_digest(digest)?)?;
+        let signature = self
+            .key
+            .sign_digest(FileDigestAlgorithm::Sha2_256, &signed)?;
+        let signature_algorithm = match self.key {
+            SigningKey::Rsa(_) => algorithm_identifier(OID_RSA_ENCRYPTION, true),
+            SigningKey::EcdsaP256(_) => algorithm_identifier(OID_ECDSA_WITH_SHA256, false),
+        };
+        let digest_algorithm = algorithm_identifier(OID_SHA256, true);
+
+        let signer_info = [
+            der(INTEGER, &[1]),
+            self.issuer_and_serial.clone(),
+            digest_algorithm.clone(),
+            signature_algorithm,
+            der(OCTET_STRING, &signature),
+        ]
+        .concat();
+        let signed_data = [
+            der(INTEGER, &[1]),
+            der(SET, &digest_algorithm),
+            der(SEQUENCE, &der(OBJECT_IDENTIFIER, OID_PKCS7_DATA)),
+            der(SET, &der(SEQUENCE, &signer_info)),
+        ]
+        .concat();
+        let content_info = [
+            der(OBJECT_IDENTIFIER, OID_PKCS7_SIGNED_DATA),
+            der(CONTEXT_0, &der(SEQUENCE, &signed_data)),
+        ]
+        .concat();
+        Ok(der(SEQUENCE, &content_info))
+    }
+
+    /// Sign the fs-verity digest of a file's content, returning the base64
+    /// encoded signature as stored within the signature header.
+    pub(crate) fn sign_content(&self, content: &[u8]) -> Result<String, RPMError> {
+        Ok(base64::encode(self.sign_digest(&digest(content)?)?))
+    }
+}
+
+/// Checks fs-verity file signatures with an X.509 certificate.
+#[derive(Debug)]
+pub struct VerityVerifier {
+    key: VerifyingKey,
+    issuer_and_serial: Vec<u8>,
+}
+
+impl VerityVerifier {
+    /// Load a PEM or DER encoded X.509 certificate.
+    pub fn load_from_certificate(input: &[u8]) -> Result<Self, RPMError> {
+        let certificate = Certificate::load(input)?;
+        Ok(Self {
+            issuer_and_serial: issuer_and_serial(&certificate),
+            key: certificate.key,
+        })
+    }
+
+    /// Check a DER encoded PKCS#7 signature of an fs-verity file digest.
+    ///
+    /// Signatures of other certificates yield [`RPMError::KeyNotFoundError`].
+    pub fn verify_digest(&self, digest: &[u8], signature: &[u8]) -> Result<(), RPMError> {
+        let (content_info, _) = der_read(signature, SEQUENCE)?;
+        let (oid, rest) = der_read(content_info, OBJECT_IDENTIFIER)?;
+        if oid != OID_PKCS7_SIGNED_DATA {
+            return Err(RPMError::InvalidVeritySignature(
+                "expected PKCS#7 signed data",
+            ));
+        }
+        let (signed_data, _) = der_read(rest, CONTEXT_0)?;
+        let (signed_data, _) = der_read(signed_data, SEQUENCE)?;
+        let (_version, rest) = der_read(signed_data, INTEGER)?;
+        let (_digest_algorithms, rest) = der_read(rest, SET)?;
+        let (_content_info, mut rest) = der_read(rest, SEQUENCE)?;
+        for optional in [CONTEXT_0, CONTEXT_1] {
+            // certificates and revocation lists
+            if rest.first() == Some(&optional) {
+                rest = der_read(rest, optional)?.1;
+            }
+        }
+        let (signer_infos, _) = der_read(rest, SET)?;
+        let (signer_info, _) = der_read(signer_infos, SEQUENCE)?;
+        let (_version, rest) = der_read(signer_info, INTEGER)?;
+        let (_, after_issuer_and_serial) = der_read(rest, SEQUENCE)?;
+        let issuer_and_serial = &rest[..rest.len() - after_issuer_and_serial.len()];
+        let rest = after_issuer_and_serial;
+        if issuer_and_serial != self.issuer_and_serial {
+            return Err(RPMError::KeyNotFoundError {
+                key_ref: hex::encode(issuer_and_serial),
+            });
+        }
+        let (digest_algorithm, rest) = der_read(rest, SEQUENCE)?;
+        if der_read(digest_algorithm, OBJECT_IDENTIFIER)?.0 != OID_SHA256 {
+            return Err(RPMError::InvalidVeritySignature(
+                "only SHA-256 signatures are supported",
+            ));
+        }
+        if rest.first() == Some(&CONTEXT_0) {
+            return Err(RPMError::InvalidVeritySignature(
+                "authenticated attributes are not supported",
+            ));
+        }
+        let (_signature_algorithm, rest) = der_read(rest, SEQUENCE)?;
+        let (signature, _) = der_read(rest, OCTET_STRING)?;
+
+        let signed = FileDigestAlgorithm::Sha2_256.digest(&formatted_digest(digest)?)?;
+        self.key
+            .verify_digest(FileDigestAlgorithm::Sha2_256, &signed, signature)
+            .map_err(|source| RPMError::VerificationError {
+                source,
+                key_ref: hex::encode(&self.issuer_and_serial),
+            })
+    }
+
+    /// Check a base64 encoded signature, as stored within the signature header,
+    /// of a file's content read from `input`.
+    pub(crate) fn verify_reader<R: Read>(&self, input: R, signature: &str) -> Result<(), RPMError> {
+        let signature = base64::decode(signature)
+            .map_err(|_| RPMError::InvalidVeritySignature("the signature is not base64 encoded"))?;
+        self.verify_digest(&digest_reader(input)?, &signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::x509::test::load_keys;
+
+    #[test]
+    fn digest_of_merkle_tree() -> Result<(), RPMError> {
+        // as printed by `fsverity digest`
+        assert_eq!(
+            "3d248ca542a24fc62d1c43b916eae5016878e2533c88238480b26128a1f1af95",
+            hex::encode(digest(&[])?)
+        );
+        // two levels of hash blocks, with partially filled blocks
+        let content = (0..FS_VERITY_BLOCK_SIZE * 130 + 10)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        assert_eq!(digest(&content)?, digest_reader(content.as_slice())?);
+        assert_ne!(digest(&content)?, digest(&content[1..])?);
+        Ok(())
+    }
+
+    #[test]
+    fn sign_and_verify_digest() -> Result<(), RPMError> {
+        let file_digest = digest(b"some file content")?;
+        let other_digest = digest(b"other file content")?;
+        let [(rsa_key, rsa_cert), (ecdsa_key, ecdsa_cert)] = load_keys();
+        for (key, cert, other_cert) in [
+            (&rsa_key, &rsa_cert, &ecdsa_cert),
+            (&ecdsa_key, &ecdsa_cert, &rsa_cert),
+        ] {
+            let signer = VeritySigner::load_from_pem(key, cert)?;
+            let verifier = VerityVerifier::load_from_certificate(cert)?;
+            let signature = signer.sign_digest(&file_digest)?;
+            verifier.verify_digest(&file_digest, &signature)?;
+            assert!(matches!(
+                verifier.verify_digest(&other_digest, &signature),
+                Err(RPMError::VerificationError { .. })
+            ));
+            assert!(matches!(
+                VerityVerifier::load_from_certificate(other_cert)?
+                    .verify_digest(&file_digest, &signature),
+                Err(RPMError::KeyNotFoundError { .. })
+            ));
+        }
+        assert!(matches!(
+            VeritySigner::load_from_pem(&rsa_key, &ecdsa_cert),
+            Err(RPMError::KeyLoadError { .. })
+        ));
+        Ok(())
+    }
+}
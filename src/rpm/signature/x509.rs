@@ -0,0 +1,222 @@
+//! Private keys and X.509 certificates of the kernel keyrings,
+//! shared by IMA and fs-verity file signatures.
+//!
+//! Supported are RSA keys and ECDSA keys on NIST P-256, signing a digest
+//! with PKCS#1 v1.5 padding respectively a DER encoded ECDSA signature.
+
+use crate::errors::*;
+use crate::FileDigestAlgorithm;
+
+use p256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use p256::pkcs8::DecodePrivateKey as _;
+use rsa::pkcs1::{DecodeRsaPrivateKey, EncodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey as _, DecodePublicKey as _};
+use rsa::PublicKey;
+
+/// The hash of a digest, for the `DigestInfo` of RSA signatures.
+pub(crate) fn rsa_hash(algorithm: FileDigestAlgorithm) -> Result<rsa::Hash, RPMError> {
+    Ok(match algorithm {
+        FileDigestAlgorithm::Md5 => rsa::Hash::MD5,
+        FileDigestAlgorithm::Sha1 => rsa::Hash::SHA1,
+        FileDigestAlgorithm::Sha2_224 => rsa::Hash::SHA2_224,
+        FileDigestAlgorithm::Sha2_256 => rsa::Hash::SHA2_256,
+        FileDigestAlgorithm::Sha2_384 => rsa::Hash::SHA2_384,
+        FileDigestAlgorithm::Sha2_512 => rsa::Hash::SHA2_512,
+        algorithm => return Err(RPMError::UnsupportedFileDigestAlgorithm(algorithm)),
+    })
+}
+
+pub(crate) fn pem_str(input: &[u8]) -> Result<&str, RPMError> {
+    std::str::from_utf8(input).map_err(|e| RPMError::KeyLoadError {
+        source: Box::new(e),
+        details: "PEM encoded keys are ASCII",
+    })
+}
+
+#[derive(Debug)]
+pub(crate) enum SigningKey {
+    Rsa(Box<rsa::RsaPrivateKey>),
+    EcdsaP256(p256::ecdsa::SigningKey),
+}
+
+impl SigningKey {
+    /// Load a PEM encoded private key, PKCS#8, PKCS#1 for RSA or SEC1 for ECDSA keys.
+    pub(crate) fn load_from_pem(input: &str) -> Result<Self, RPMError> {
+        if let Ok(key) = rsa::RsaPrivateKey::from_pkcs8_pem(input)
+            .or_else(|_| rsa::RsaPrivateKey::from_pkcs1_pem(input))
+        {
+            return Ok(Self::Rsa(Box::new(key)));
+        }
+        let key = p256::SecretKey::from_pkcs8_pem(input)
+            .or_else(|_| p256::SecretKey::from_sec1_pem(input))
+            .map_err(|e| RPMError::KeyLoadError {
+                source: Box::new(e),
+                details: "expected an RSA or NIST P-256 private key",
+            })?;
+        Ok(Self::EcdsaP256(key.into()))
+    }
+
+    /// The public key as found in the `subjectPublicKey` of a certificate,
+    /// the PKCS#1 encoded RSA key or the uncompressed point.
+    pub(crate) fn subject_public_key(&self) -> Result<Vec<u8>, RPMError> {
+        Ok(match self {
+            Self::Rsa(key) => key
+                .to_public_key()
+                .to_pkcs1_der()
+                .map_err(|e| RPMError::KeyLoadError {
+                    source: Box::new(e),
+                    details: "unable to encode the RSA public key",
+                })?
+                .as_ref()
+                .to_vec(),
+            Self::EcdsaP256(key) => key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+        })
+    }
+
+    /// Sign a digest calculated with `algorithm`.
+    pub(crate) fn sign_digest(
+        &self,
+        algorithm: FileDigestAlgorithm,
+        digest: &[u8],
+    ) -> Result<Vec<u8>, RPMError> {
+        match self {
+            Self::Rsa(key) => key
+                .sign(
+                    rsa::PaddingScheme::new_pkcs1v15_sign(Some(rsa_hash(algorithm)?)),
+                    digest,
+                )
+                .map_err(|e| RPMError::SignError(Box::new(e))),
+            Self::EcdsaP256(key) => {
+                let signature: p256::ecdsa::Signature = key
+                    .sign_prehash(digest)
+                    .map_err(|e| RPMError::SignError(Box::new(e)))?;
+                Ok(signature.to_der().as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum VerifyingKey {
+    Rsa(rsa::RsaPublicKey),
+    EcdsaP256(p256::ecdsa::VerifyingKey),
+}
+
+impl VerifyingKey {
+    /// Check the signature of a digest calculated with `algorithm`.
+    pub(crate) fn verify_digest(
+        &self,
+        algorithm: FileDigestAlgorithm,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Rsa(key) => key.verify(
+                rsa::PaddingScheme::new_pkcs1v15_sign(Some(rsa_hash(algorithm)?)),
+                digest,
+                signature,
+            )?,
+            Self::EcdsaP256(key) => {
+                let signature = p256::ecdsa::Signature::from_der(signature)?;
+                key.verify_prehash(digest, &signature)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The parts of an X.509 certificate signatures are checked with.
+#[derive(Debug)]
+pub(crate) struct Certificate {
+    pub(crate) key: VerifyingKey,
+    /// The PKCS#1 encoded RSA key or the point of the ECDSA key.
+    pub(crate) subject_public_key: Vec<u8>,
+    /// IMA signatures refer to the key by the end of its identifier.
+    #[cfg(feature = "signature-ima")]
+    pub(crate) subject_key_identifier: Option<Vec<u8>>,
+    /// The DER encoded name of the issuer, which together with the serial
+    /// identifies the certificate in fs-verity signatures.
+    #[cfg(feature = "signature-verity")]
+    pub(crate) issuer: Vec<u8>,
+    /// The content of the DER encoded serial number.
+    #[cfg(feature = "signature-verity")]
+    pub(crate) serial: Vec<u8>,
+}
+
+impl Certificate {
+    /// Load a PEM or DER encoded certificate.
+    pub(crate) fn load(input: &[u8]) -> Result<Self, RPMError> {
+        let pem;
+        let der = if input.starts_with(b"-----BEGIN") {
+            pem = x509_parser::pem::parse_x509_pem(input)
+                .map_err(|e| RPMError::KeyLoadError {
+                    source: Box::new(e),
+                    details: "invalid PEM encoding of the certificate",
+                })?
+                .1;
+            pem.contents.as_slice()
+        } else {
+            input
+        };
+        let (_rest, certificate) =
+            x509_parser::parse_x509_certificate(der).map_err(|e| RPMError::KeyLoadError {
+                source: Box::new(e),
+                details: "invalid X.509 certificate",
+            })?;
+
+        let spki = certificate.public_key();
+        let key = if let Ok(key) = rsa::RsaPublicKey::from_public_key_der(spki.raw) {
+            VerifyingKey::Rsa(key)
+        } else {
+            let key =
+                p256::PublicKey::from_sec1_bytes(&spki.subject_public_key.data).map_err(|e| {
+                    RPMError::KeyLoadError {
+                        source: Box::new(e),
+                        details: "expected an RSA or NIST P-256 public key",
+                    }
+                })?;
+            VerifyingKey::EcdsaP256(key.into())
+        };
+
+        #[cfg(feature = "signature-ima")]
+        let subject_key_identifier = certificate.extensions().iter().find_map(|extension| {
+            match extension.parsed_extension() {
+                x509_parser::extensions::ParsedExtension::SubjectKeyIdentifier(id) => {
+                    Some(id.0.to_vec())
+                }
+                _ => None,
+            }
+        });
+        Ok(Self {
+            key,
+            subject_public_key: spki.subject_public_key.data.to_vec(),
+            #[cfg(feature = "signature-ima")]
+            subject_key_identifier,
+            #[cfg(feature = "signature-verity")]
+            issuer: certificate.issuer().as_raw().to_vec(),
+            #[cfg(feature = "signature-verity")]
+            serial: certificate.raw_serial().to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    /// Load an RSA and an ECDSA NIST P-256 private key with their certificates, in that order.
+    pub(crate) fn load_keys() -> [(Vec<u8>, Vec<u8>); 2] {
+        [
+            (
+                include_bytes!("../../../test_assets/ima_rsa_key.pem").to_vec(),
+                include_bytes!("../../../test_assets/ima_rsa_cert.pem").to_vec(),
+            ),
+            (
+                include_bytes!("../../../test_assets/ima_ecdsa_p256_key.pem").to_vec(),
+                include_bytes!("../../../test_assets/ima_ecdsa_p256_cert.der").to_vec(),
+            ),
+        ]
+    }
+}
@@ -2,7 +2,6 @@
 pub(crate) struct SeqCursor<'s> {
     cursors: Vec<std::io::Cursor<&'s [u8]>>,
     position: u64,
-    len: usize,
 }
 
 impl<'s> SeqCursor<'s> {
@@ -16,7 +15,6 @@ impl<'s> SeqCursor<'s> {
     {
         let cursor = std::io::Cursor::<&'s [u8]>::new(another);
         self.cursors.push(cursor);
-        self.len += another.len();
     }
 
     /// Crate a new cursor based on a slice of bytes slices.
@@ -24,21 +22,14 @@ impl<'s> SeqCursor<'s> {
     where
         'b: 's,
     {
-        let len = slices.iter().fold(0usize, |acc, slice| slice.len() + acc);
         Self {
             cursors: slices
                 .iter()
                 .map(|slice| std::io::Cursor::new(*slice))
                 .collect::<Vec<_>>(),
             position: 0u64,
-            len,
         }
     }
-
-    /// Total length of all slices summed up.
-    pub(crate) fn len(&self) -> usize {
-        self.len
-    }
 }
 
 impl<'s> std::io::Read for SeqCursor<'s> {
@@ -92,18 +83,15 @@ mod test {
         let c2 = vec![2u8; 17];
         let c3 = vec![3u8; 17];
 
-        let mut buf = Vec::<u8>::with_capacity(17 * 3);
-        unsafe {
-            buf.set_len(17 * 3);
-        }
+        let mut buf = [0u8; 17 * 3];
         let mut sq = SeqCursor::new(&[c1.as_slice(), c2.as_slice(), c3.as_slice()]);
 
         sq.seek(std::io::SeekFrom::Current(16)).unwrap();
-        sq.read(&mut buf[0..4]).unwrap();
+        sq.read_exact(&mut buf[0..4]).unwrap();
         assert_eq!(buf[0..4].to_vec(), vec![1u8, 2u8, 2u8, 2u8]);
 
         sq.seek(std::io::SeekFrom::Current(12)).unwrap();
-        sq.read(&mut buf[4..8]).unwrap();
+        sq.read_exact(&mut buf[4..8]).unwrap();
         assert_eq!(buf[4..8].to_vec(), vec![2u8, 2u8, 3u8, 3u8]);
     }
 }
@@ -47,7 +47,7 @@ fn test_rpm_header_base(package: RPMPackage) -> Result<(), Box<dyn std::error::E
     assert!(metadata.signature.index_entries[0].num_items == 16);
     assert_eq!(1156, metadata.signature.index_header.header_size);
 
-    let expected_data = vec![
+    let expected_data = [
         (
             16,
             IndexData::Bin(vec![
@@ -297,7 +297,7 @@ fn test_region_tag() -> Result<(), Box<dyn std::error::Error>> {
 
     let data = possible_binary.unwrap();
 
-    let (_, entry) = IndexEntry::<IndexSignatureTag>::parse(&data)?;
+    let (_, entry) = IndexEntry::<IndexSignatureTag>::parse(data)?;
 
     assert_eq!(entry.tag, IndexSignatureTag::HEADER_SIGNATURES);
     assert_eq!(entry.data.to_u32(), IndexData::Bin(Vec::new()).to_u32());
@@ -305,3 +305,1478 @@ fn test_region_tag() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_selinux_contexts_and_xattrs_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("labeled", "1.0.0", "MIT", "x86_64", "labeled files")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml")
+                .selinux_context("system_u:object_r:etc_t:s0"),
+        )?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome")
+                .selinux_context("system_u:object_r:bin_t:s0")
+                .xattr("user.origin", "fez")
+                .xattr("security.capability", vec![0x01, 0x00, 0x00, 0x02]),
+        )?
+        .with_file(
+            "./test_assets/awesome.xml",
+            RPMFileOptions::new("/usr/share/awesome/awesome.xml"),
+        )?
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+
+    let entries = pkg.metadata.header.get_file_entries()?;
+    assert_eq!(3, entries.len());
+
+    let config = &entries[0];
+    assert_eq!(
+        std::path::PathBuf::from("/etc/awesome/config.toml"),
+        config.path
+    );
+    assert_eq!(
+        Some("system_u:object_r:etc_t:s0"),
+        config.selinux_context.as_deref()
+    );
+    assert!(config.xattrs.is_empty());
+
+    let bin = &entries[1];
+    assert_eq!(
+        Some("system_u:object_r:bin_t:s0"),
+        bin.selinux_context.as_deref()
+    );
+    assert_eq!(2, bin.xattrs.len());
+    assert_eq!(Some(&b"fez".to_vec()), bin.xattrs.get("user.origin"));
+    assert_eq!(
+        Some(&vec![0x01, 0x00, 0x00, 0x02]),
+        bin.xattrs.get("security.capability")
+    );
+
+    let xml = &entries[2];
+    assert_eq!(None, xml.selinux_context);
+    assert!(xml.xattrs.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_scriptlets_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("scripted", "1.0.0", "MIT", "noarch", "with scriptlets")
+        .pre_install_script("echo preinst")
+        .post_install_script(Scriptlet::new("print('hello')").prog(vec!["/usr/bin/python3"]))
+        .pre_trans_script(Scriptlet::new("print(\"pretrans\")").prog(vec!["<lua>"]))
+        .post_trans_script(
+            Scriptlet::new("echo posttrans")
+                .prog(vec!["/bin/bash", "-e"])
+                .flags(RPMSCRIPT_FLAG_EXPAND | RPMSCRIPT_FLAG_CRITICAL),
+        )
+        .verify_script("test -f /etc/scripted.conf")
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    assert_eq!(
+        "/bin/sh",
+        header.get_entry_string_data(IndexTag::RPMTAG_PREINPROG)?
+    );
+    assert_eq!(
+        ["/bin/bash", "-e"],
+        header.get_entry_string_array_data(IndexTag::RPMTAG_POSTTRANSPROG)?
+    );
+
+    let scriptlets = header.get_scriptlets()?;
+    let types = scriptlets.iter().map(|(ty, _)| *ty).collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ScriptletType::PreTransaction,
+            ScriptletType::PreInstall,
+            ScriptletType::PostInstall,
+            ScriptletType::PostTransaction,
+            ScriptletType::Verify,
+        ],
+        types
+    );
+
+    assert!(scriptlets[0].1.is_lua());
+    assert_eq!(Scriptlet::new("echo preinst"), scriptlets[1].1);
+    assert_eq!(vec!["/usr/bin/python3"], scriptlets[2].1.program);
+    assert_eq!(
+        Scriptlet::new("echo posttrans")
+            .prog(vec!["/bin/bash", "-e"])
+            .flags(RPMSCRIPT_FLAG_EXPAND | RPMSCRIPT_FLAG_CRITICAL),
+        scriptlets[3].1
+    );
+    assert!(header.get_scriptlet(ScriptletType::PostUninstall).is_err());
+
+    // interpreters are required, except for the embedded lua
+    let requires = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    let require_flags = header.get_entry_i32_array_data(IndexTag::RPMTAG_REQUIREFLAGS)?;
+    let python = requires
+        .iter()
+        .position(|name| name == "/usr/bin/python3")
+        .expect("python interpreter must be required");
+    assert_eq!(
+        (RPMSENSE_INTERP | RPMSENSE_SCRIPT_POST) as i32,
+        require_flags[python]
+    );
+    assert!(!requires.iter().any(|name| name == "<lua>"));
+
+    Ok(())
+}
+
+#[test]
+fn test_triggers_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("plugin-host", "1.0.0", "MIT", "noarch", "reacts to plugins")
+        .add_trigger(
+            Trigger::new(TriggerType::Install, "plugin-host --rescan")
+                .condition(Dependency::any("plugin-a"))
+                .condition(Dependency::greater_eq("plugin-b", "2.0")),
+        )
+        .add_trigger(
+            Trigger::new(
+                TriggerType::PostUninstall,
+                Scriptlet::new("import os").prog(vec!["/usr/bin/python3"]),
+            )
+            .condition(Dependency::any("plugin-a")),
+        )
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    assert_eq!(
+        vec![0, 0, 1],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_TRIGGERINDEX)?
+    );
+    assert_eq!(
+        vec![
+            RPMSENSE_TRIGGERIN as i32,
+            (RPMSENSE_TRIGGERIN | RPMSENSE_GREATER | RPMSENSE_EQUAL) as i32,
+            RPMSENSE_TRIGGERPOSTUN as i32,
+        ],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_TRIGGERFLAGS)?
+    );
+
+    let triggers = header.get_triggers()?;
+    assert_eq!(2, triggers.len());
+    assert_eq!(
+        Trigger::new(TriggerType::Install, "plugin-host --rescan")
+            .condition(Dependency::any("plugin-a"))
+            .condition(Dependency::greater_eq("plugin-b", "2.0")),
+        triggers[0]
+    );
+    assert_eq!(TriggerType::PostUninstall, triggers[1].ty);
+    assert_eq!(vec!["/usr/bin/python3"], triggers[1].scriptlet.program);
+
+    let result = RPMBuilder::new("broken", "1.0.0", "MIT", "noarch", "broken trigger")
+        .add_trigger(
+            Trigger::new(
+                TriggerType::Install,
+                Scriptlet::new("true").prog(vec!["/bin/bash", "-e"]),
+            )
+            .condition(Dependency::any("foo")),
+        )
+        .build();
+    assert!(matches!(result, Err(RPMError::InvalidTrigger(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_file_triggers_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("plugin-host", "1.0.0", "MIT", "noarch", "reacts to plugins")
+        .add_file_trigger(
+            FileTrigger::new(
+                TriggerType::Install,
+                FileTriggerScope::Package,
+                "plugin-host --register",
+            )
+            .prefix("/usr/lib/plugin-host/plugins/")
+            .prefix("/usr/share/plugin-host/")
+            .priority(500),
+        )
+        .add_file_trigger(
+            FileTrigger::new(
+                TriggerType::PostUninstall,
+                FileTriggerScope::Transaction,
+                "plugin-host --rescan",
+            )
+            .prefix("/usr/lib/plugin-host/plugins/"),
+        )
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    assert_eq!(
+        vec![500],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_FILETRIGGERPRIORITIES)?
+    );
+    assert_eq!(
+        vec![RPMSENSE_TRIGGERPOSTUN as i32],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_TRANSFILETRIGGERFLAGS)?
+    );
+    assert!(header.get_triggers()?.is_empty());
+
+    let file_triggers = header.get_file_triggers()?;
+    assert_eq!(2, file_triggers.len());
+    assert_eq!(
+        vec!["/usr/lib/plugin-host/plugins/", "/usr/share/plugin-host/"],
+        file_triggers[0].prefixes
+    );
+    assert_eq!(500, file_triggers[0].priority);
+    assert_eq!(FileTriggerScope::Transaction, file_triggers[1].scope);
+    assert_eq!(TriggerType::PostUninstall, file_triggers[1].ty);
+    assert_eq!(DEFAULT_FILE_TRIGGER_PRIORITY, file_triggers[1].priority);
+
+    let result = RPMBuilder::new("broken", "1.0.0", "MIT", "noarch", "broken trigger")
+        .add_file_trigger(
+            FileTrigger::new(TriggerType::PreInstall, FileTriggerScope::Package, "true")
+                .prefix("/usr/lib/"),
+        )
+        .build();
+    assert!(matches!(result, Err(RPMError::InvalidTrigger(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_rpmlib_requires() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "x86_64", "some package")
+        .compression("zstd".parse()?)
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome").caps("cap_net_raw=ep"),
+        )?
+        .post_install_script(Scriptlet::new("print('hello')").prog(vec![LUA_SCRIPTLET_INTERPRETER]))
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    let names = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    let flags = header.get_entry_i32_array_data(IndexTag::RPMTAG_REQUIREFLAGS)?;
+    let rpmlib_names: Vec<&str> = names
+        .iter()
+        .zip(flags)
+        .filter(|(_, flags)| *flags as u32 & RPMSENSE_RPMLIB != 0)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(
+        vec![
+            "rpmlib(CompressedFileNames)",
+            "rpmlib(FileDigests)",
+            "rpmlib(PayloadFilesHavePrefix)",
+            "rpmlib(PayloadIsZstd)",
+            "rpmlib(FileCaps)",
+            "rpmlib(BuiltinLuaScripts)",
+        ],
+        rpmlib_names
+    );
+    let caps = header
+        .get_file_entries()?
+        .into_iter()
+        .map(|entry| entry.caps)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![None, Some("cap_net_raw=ep".to_owned())], caps);
+
+    let pkg = RPMBuilder::new("empty", "1.0.0", "MIT", "noarch", "nothing").build()?;
+    let names = pkg
+        .metadata
+        .header
+        .get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    assert!(!names.iter().any(|name| name.starts_with("rpmlib(")));
+
+    Ok(())
+}
+
+#[test]
+fn test_large_files() -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome"),
+        )?;
+    // pretend every file is beyond 4 GiB
+    builder.large_file_threshold = 0;
+    let pkg = builder.build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    let config = std::fs::read("./test_assets/awesome.toml")?;
+    let script = std::fs::read("./test_assets/awesome.py")?;
+
+    // the stripped format only refers to the file index, padded like newc
+    let mut archive = Vec::new();
+    for (idx, content) in [&config, &script].iter().enumerate() {
+        archive.extend(format!("07070X{:08x}", idx).as_bytes());
+        archive.extend([0; 2]);
+        archive.extend(content.iter());
+        archive.resize(archive.len() + (4 - content.len() % 4) % 4, 0);
+    }
+    assert!(pkg.content.starts_with(&archive));
+    assert_eq!(
+        archive.len() as u64 + 124,
+        pkg.metadata.signature.get_payload_size()?
+    );
+
+    assert_eq!(
+        vec![config.len() as i64, script.len() as i64],
+        header.get_entry_i64_array_data(IndexTag::RPMTAG_LONGFILESIZES)?
+    );
+    assert!(header
+        .get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)
+        .is_err());
+    assert_eq!(
+        (config.len() + script.len()) as i64,
+        header.get_entry_i64_data(IndexTag::RPMTAG_LONGSIZE)?
+    );
+    assert!(header.get_entry_i32_data(IndexTag::RPMTAG_SIZE).is_err());
+
+    let names = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    assert!(names.contains(&"rpmlib(LargeFiles)".to_string()));
+
+    let sizes: Vec<usize> = header
+        .get_file_entries()?
+        .iter()
+        .map(|entry| entry.size)
+        .collect();
+    assert_eq!(vec![config.len(), script.len()], sizes);
+
+    Ok(())
+}
+
+#[test]
+fn test_rich_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+    let agent: RichDependency = "(our-agent-el8 or our-agent-el9)".parse()?;
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .requires(agent)
+        .requires(Dependency::any("wget"))
+        .recommends(RichDependency::If {
+            then: Box::new(Dependency::any("agent-selinux").into()),
+            condition: Box::new(Dependency::any("selinux-policy").into()),
+            otherwise: None,
+        })
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    let names = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    assert!(names.contains(&"(our-agent-el8 or our-agent-el9)".to_string()));
+    assert!(names.contains(&"rpmlib(RichDependencies)".to_string()));
+    assert_eq!(
+        vec!["(agent-selinux if selinux-policy)"],
+        header.get_entry_string_array_data(IndexTag::RPMTAG_RECOMMENDNAME)?
+    );
+    assert_eq!(
+        vec![RPMSENSE_ANY as i32],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_RECOMMENDFLAGS)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_package_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "a longer description")
+        .summary("a summary")
+        .group("Applications/System")
+        .url("https://example.com/test")
+        .vendor("Example Corp")
+        .packager("Jane Doe <jane@example.com>")
+        .bug_url("https://example.com/test/issues")
+        .build_host("builder.example.com")
+        .build_time(1_600_000_000)
+        .dist_tag("el9")
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    assert_eq!("a summary", header.get_summary()?);
+    assert_eq!("a longer description", header.get_description()?);
+    assert_eq!("Applications/System", header.get_group()?);
+    assert_eq!("https://example.com/test", header.get_url()?);
+    assert_eq!("Example Corp", header.get_vendor()?);
+    assert_eq!("Jane Doe <jane@example.com>", header.get_packager()?);
+    assert_eq!("https://example.com/test/issues", header.get_bug_url()?);
+    assert_eq!("builder.example.com", header.get_build_host()?);
+    assert_eq!(1_600_000_000, header.get_build_time()?);
+    assert_eq!("el9", header.get_dist_tag()?);
+    assert!(matches!(header.get_vcs(), Err(RPMError::TagNotFound(_))));
+
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "a description").build()?;
+    assert_eq!("a description", pkg.metadata.header.get_summary()?);
+    assert_eq!("Unspecified", pkg.metadata.header.get_group()?);
+
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_reproducible_build() -> Result<(), Box<dyn std::error::Error>> {
+    let (signing_key, _) = crate::signature::pgp::test::load_asc_keys();
+    let build = || -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?
+            .with_signing_time(1_600_000_000);
+        let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+            .compression("gzip".parse()?)
+            .source_date(1_600_000_000)
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .with_file(
+                "./test_assets/awesome.py",
+                RPMFileOptions::new("/usr/bin/awesome"),
+            )?
+            .build_and_sign(signer)?;
+        let mut buf = Vec::new();
+        pkg.write(&mut buf)?;
+        Ok(buf)
+    };
+
+    let first = build()?;
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let second = build()?;
+    assert!(first == second, "builds differ");
+
+    let pkg = RPMPackage::parse(&mut first.as_slice())?;
+    let header = &pkg.metadata.header;
+    assert_eq!(1_600_000_000, header.get_build_time()?);
+    assert!(header
+        .get_entry_i32_array_data(IndexTag::RPMTAG_FILEMTIMES)?
+        .iter()
+        .all(|mtime| *mtime <= 1_600_000_000));
+
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_ecc_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    for (signing_key, verification_key) in crate::signature::pgp::test::load_ecc_asc_keys().iter() {
+        let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key)?;
+        let verifier = signature::pgp::Verifier::load_from_asc_bytes(verification_key)?;
+        let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .build_and_sign(signer)?;
+
+        let mut buf = Vec::new();
+        pkg.write(&mut buf)?;
+        let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+        let signature = &pkg.metadata.signature;
+        for tag in &[
+            IndexSignatureTag::RPMSIGTAG_DSA,
+            IndexSignatureTag::RPMSIGTAG_GPG,
+        ] {
+            assert!(signature.find_entry_or_err(tag).is_ok());
+        }
+        for tag in &[
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP,
+        ] {
+            assert!(signature.find_entry_or_err(tag).is_err());
+        }
+        pkg.verify_signature(verifier)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_header_only_signature() -> Result<(), Box<dyn std::error::Error>> {
+    let (signing_key, verification_key) = crate::signature::pgp::test::load_asc_keys();
+    let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?;
+    let verifier = signature::pgp::Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .compression("gzip".parse()?)
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .build_and_sign_with_mode(&signer, signature::SignatureMode::HeaderOnly)?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let mut pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let signature = &pkg.metadata.signature;
+    for tag in &[
+        IndexSignatureTag::RPMSIGTAG_RSA,
+        IndexSignatureTag::RPMSIGTAG_SHA256,
+        IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE,
+    ] {
+        assert!(signature.find_entry_or_err(tag).is_ok());
+    }
+    for tag in &[
+        IndexSignatureTag::RPMSIGTAG_PGP,
+        IndexSignatureTag::RPMSIGTAG_MD5,
+        IndexSignatureTag::RPMSIGTAG_SHA1,
+    ] {
+        assert!(signature.find_entry_or_err(tag).is_err());
+    }
+    assert_eq!(
+        FileDigestAlgorithm::Sha2_256,
+        pkg.metadata.header.get_payload_digest()?.algorithm()
+    );
+    pkg.verify_signature(&verifier)?;
+
+    let mut tampered = pkg.content.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let tampered = RPMPackage {
+        metadata: RPMPackage::parse(&mut buf.as_slice())?.metadata,
+        content: tampered,
+    };
+    assert!(matches!(
+        tampered.verify_signature(&verifier),
+        Err(RPMError::PayloadDigestMismatch)
+    ));
+
+    // switching modes replaces the signature header
+    pkg.sign(&signer)?;
+    assert!(pkg
+        .metadata
+        .signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_PGP)
+        .is_ok());
+    pkg.sign_with_mode(&signer, signature::SignatureMode::HeaderOnly)?;
+    assert!(pkg
+        .metadata
+        .signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_PGP)
+        .is_err());
+    pkg.verify_signature(&verifier)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_verification_report() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::{CheckOutcome, SignedRange, VerificationStatus};
+
+    let (signing_key, verification_key) = crate::signature::pgp::test::load_asc_keys();
+    let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_signing_time(1_600_000_000);
+    let verifier = signature::pgp::Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+    let [(_, other_key), _] = crate::signature::pgp::test::load_ecc_asc_keys();
+    let other_verifier = signature::pgp::Verifier::load_from_asc_bytes(&other_key)?;
+
+    let builder = || {
+        RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package").with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )
+    };
+
+    let unsigned = builder()?.build()?;
+    let report = unsigned.verification_report(&verifier)?;
+    assert_eq!(VerificationStatus::Unsigned, report.status());
+    assert!(report.signatures.is_empty());
+    assert!(report
+        .digests
+        .iter()
+        .all(|check| check.outcome == CheckOutcome::Valid));
+
+    let pkg = builder()?.build_and_sign(&signer)?;
+    let report = pkg.verification_report(&verifier)?;
+    assert_eq!(VerificationStatus::Verified, report.status());
+    assert_eq!(2, report.signatures.len());
+    for check in &report.signatures {
+        assert!(check.key_found());
+        assert_eq!(
+            Some(signature::algorithm::AlgorithmType::RSA),
+            check.info.algorithm
+        );
+        assert_eq!(
+            Some(1_600_000_000),
+            check.info.created.map(|created| created.timestamp())
+        );
+    }
+    assert_eq!(1, report.verified_key_ids().len());
+    let ranges = report
+        .digests
+        .iter()
+        .map(|check| check.range)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            SignedRange::HeaderAndPayload,
+            SignedRange::Header,
+            SignedRange::Payload
+        ],
+        ranges
+    );
+
+    let report = pkg.verification_report(&other_verifier)?;
+    assert_eq!(VerificationStatus::UnknownKey, report.status());
+    assert!(report.signatures.iter().all(|check| !check.key_found()));
+
+    let tampered = RPMPackage {
+        metadata: builder()?.build_and_sign(&signer)?.metadata,
+        content: pkg.content.iter().map(|byte| byte ^ 1).collect(),
+    };
+    let report = tampered.verification_report(&verifier)?;
+    assert_eq!(VerificationStatus::BadSignature, report.status());
+    let outcome = |range| {
+        report
+            .signatures
+            .iter()
+            .find(|check| check.range == range)
+            .map(|check| check.outcome.clone())
+    };
+    assert_eq!(Some(CheckOutcome::Valid), outcome(SignedRange::Header));
+    assert!(matches!(
+        outcome(SignedRange::HeaderAndPayload),
+        Some(CheckOutcome::Invalid(_))
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_signature_introspection() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::{algorithm::AlgorithmType, SignedRange, Signing};
+
+    // signed by gpg with a version 4 signature, recording the fingerprint
+    let pkg = RPMPackage::parse(&mut std::io::BufReader::new(std::fs::File::open(
+        file_signatures_test_rpm_file_path(),
+    )?))?;
+    let signatures = pkg.metadata.signature.get_signatures()?;
+    assert_eq!(
+        vec![
+            (IndexSignatureTag::RPMSIGTAG_RSA, SignedRange::Header),
+            (
+                IndexSignatureTag::RPMSIGTAG_PGP,
+                SignedRange::HeaderAndPayload
+            )
+        ],
+        signatures
+            .iter()
+            .map(|signature| (signature.tag, signature.range))
+            .collect::<Vec<_>>()
+    );
+    for signature in &signatures {
+        assert_eq!(Some(AlgorithmType::RSA), signature.info.algorithm);
+        assert_eq!(
+            Some(FileDigestAlgorithm::Sha2_256),
+            signature.info.hash_algorithm
+        );
+        assert_eq!(Some("a699c3e3d7715607"), signature.info.key_id.as_deref());
+        assert_eq!(
+            Some("3f2502731695ec6c2bbd49b4a699c3e3d7715607"),
+            signature.info.fingerprint.as_deref()
+        );
+        assert_eq!(
+            Some(1_610_515_507),
+            signature.info.created.map(|created| created.timestamp())
+        );
+    }
+
+    // version 3 signatures carry no fingerprint
+    let pkg = RPMPackage::parse(&mut std::io::BufReader::new(std::fs::File::open(
+        test_rpm_file_path(),
+    )?))?;
+    let info = pkg
+        .metadata
+        .signature
+        .get_signature_info(IndexSignatureTag::RPMSIGTAG_RSA)?;
+    assert_eq!(Some("24c6a8a7f4a80eb5"), info.key_id.as_deref());
+    assert_eq!(None, info.fingerprint);
+
+    let [(signing_key, _), _] = crate::signature::pgp::test::load_ecc_asc_keys();
+    let signer =
+        signature::pgp::Signer::load_from_asc_bytes(&signing_key)?.with_signing_time(1_600_000_000);
+    let builder = || {
+        RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package").with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )
+    };
+    assert!(builder()?
+        .build()?
+        .metadata
+        .signature
+        .get_signatures()?
+        .is_empty());
+
+    let pkg = builder()?.build_and_sign(&signer)?;
+    let signatures = pkg.metadata.signature.get_signatures()?;
+    assert_eq!(2, signatures.len());
+    for signature in &signatures {
+        assert_eq!(signer.algorithm(), signature.info.algorithm.unwrap());
+        let key_id = signature.info.key_id.as_deref().unwrap();
+        assert!(signature
+            .info
+            .fingerprint
+            .as_deref()
+            .unwrap()
+            .ends_with(key_id));
+        assert_eq!(
+            Some(1_600_000_000),
+            signature.info.created.map(|created| created.timestamp())
+        );
+    }
+    assert!(matches!(
+        pkg.metadata
+            .signature
+            .get_signature_info(IndexSignatureTag::RPMSIGTAG_RSA),
+        Err(RPMError::TagNotFound(_))
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_detached_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::Signing;
+
+    let (signing_key, verification_key) = crate::signature::pgp::test::load_asc_keys();
+    // stands in for a remote signing service
+    let remote_signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_signing_time(1_600_000_000);
+    let verifier = signature::pgp::Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+
+    let build = || {
+        RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+            .source_date(1_600_000_000)
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .build()
+    };
+
+    let mut pkg = build()?;
+    let data = pkg.signing_data()?;
+    let header_signature = remote_signer.sign(data.header())?;
+    let header_and_payload_signature = remote_signer.sign(data.header_and_payload())?;
+
+    // mixed up signatures are refused
+    assert!(matches!(
+        pkg.apply_signatures(
+            &verifier,
+            &header_and_payload_signature,
+            Some(&header_signature)
+        ),
+        Err(RPMError::InvalidDetachedSignature(_))
+    ));
+
+    pkg.apply_signatures(
+        &verifier,
+        &header_signature,
+        Some(&header_and_payload_signature),
+    )?;
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    assert!(pkg
+        .metadata
+        .signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_PGP)
+        .is_ok());
+    pkg.verify_signature(&verifier)?;
+
+    // a signature of another package is refused
+    let mut other = RPMBuilder::new("other", "1.0.0", "MIT", "noarch", "other package")
+        .source_date(1_600_000_000)
+        .build()?;
+    assert!(other
+        .apply_signatures(&verifier, &header_signature, None)
+        .is_err());
+
+    // so is a forged signature, even if the digest prefix in the packet matches
+    let mut pkg = build()?;
+    let header_signature = remote_signer.sign(pkg.signing_data()?.header())?;
+    let mut forged = header_signature.clone();
+    *forged.last_mut().unwrap() ^= 0xff;
+    assert!(pkg.apply_signatures(&verifier, &forged, None).is_err());
+
+    pkg.apply_signatures(&verifier, &header_signature, None)?;
+    assert!(pkg
+        .metadata
+        .signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_PGP)
+        .is_err());
+    pkg.verify_signature(&verifier)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_package_file_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::{SignedRange, VerificationStatus};
+
+    let (signing_key, verification_key) = crate::signature::pgp::test::load_asc_keys();
+    let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?;
+    let verifier = signature::pgp::Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+    let [(ecc_signing_key, ecc_verification_key), _] =
+        crate::signature::pgp::test::load_ecc_asc_keys();
+    let ecc_signer = signature::pgp::Signer::load_from_asc_bytes(ecc_signing_key.as_ref())?;
+    let mut keyring = signature::pgp::Keyring::load_from_bytes(verification_key.as_ref())?;
+    keyring.add_keys_from_bytes(ecc_verification_key.as_ref())?;
+
+    let mut dir = cargo_manifest_dir();
+    dir.push("target/package_file");
+    std::fs::create_dir_all(&dir)?;
+
+    let read = |path: &std::path::Path| -> Result<RPMPackage, RPMError> {
+        RPMPackage::parse(&mut std::io::BufReader::new(std::fs::File::open(path)?))
+    };
+    let header_and_payload = |pkg: &RPMPackage| -> Result<Vec<u8>, RPMError> {
+        let mut buf = Vec::new();
+        pkg.metadata.header.write(&mut buf)?;
+        buf.extend_from_slice(&pkg.content);
+        Ok(buf)
+    };
+
+    // unsigned packages have room for signatures
+    let path = dir.join("built.rpm");
+    RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .build()?
+        .write(&mut std::fs::File::create(&path)?)?;
+    let unsigned = read(&path)?;
+    let len = std::fs::metadata(&path)?.len();
+
+    let mut file = RPMPackageFile::open(&path)?;
+    file.sign(&signer)?;
+    assert!(file.write()?);
+    assert_eq!(len, std::fs::metadata(&path)?.len());
+    let pkg = read(&path)?;
+    assert_eq!(header_and_payload(&unsigned)?, header_and_payload(&pkg)?);
+    pkg.verify_signature(&verifier)?;
+
+    let mut file = RPMPackageFile::open(&path)?;
+    file.add_signature(&ecc_signer, signature::SignatureMode::HeaderOnly)?;
+    assert!(file.write()?);
+    let report = read(&path)?.verification_report(&keyring)?;
+    assert_eq!(VerificationStatus::Verified, report.status());
+    assert_eq!(3, report.signatures.len());
+
+    let mut file = RPMPackageFile::open(&path)?;
+    file.delete_signatures();
+    assert!(file.write()?);
+    let pkg = read(&path)?;
+    assert_eq!(header_and_payload(&unsigned)?, header_and_payload(&pkg)?);
+    assert_eq!(
+        VerificationStatus::Unsigned,
+        pkg.verification_report(&keyring)?.status()
+    );
+
+    // packages signed by rpmsign keep the remaining reserved space
+    let path = dir.join("vendor.rpm");
+    std::fs::copy(test_rpm_file_path(), &path)?;
+    let vendor = read(&path)?;
+    let mut file = RPMPackageFile::open(&path)?;
+    file.sign(&signer)?;
+    file.add_signature(&ecc_signer, signature::SignatureMode::default())?;
+    assert!(file.write()?);
+    let pkg = read(&path)?;
+    assert_eq!(header_and_payload(&vendor)?, header_and_payload(&pkg)?);
+    let report = pkg.verification_report(&keyring)?;
+    assert_eq!(VerificationStatus::Verified, report.status());
+    assert_eq!(
+        2,
+        report
+            .signatures
+            .iter()
+            .filter(|check| check.range == SignedRange::HeaderAndPayload)
+            .count()
+    );
+
+    // packages signed on build or by `RPMPackage::sign` keep the remaining reserved space
+    let build = || -> Result<RPMPackage, RPMError> {
+        RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .build_and_sign(&signer)
+    };
+    let mut resigned = build()?;
+    resigned.sign(&ecc_signer)?;
+    for (name, pkg) in [("signed.rpm", build()?), ("resigned.rpm", resigned)] {
+        let path = dir.join(name);
+        pkg.write(&mut std::fs::File::create(&path)?)?;
+        // the signatures took their room from the reserved space
+        assert_eq!(len, std::fs::metadata(&path)?.len());
+
+        let mut file = RPMPackageFile::open(&path)?;
+        file.add_signature(&ecc_signer, signature::SignatureMode::default())?;
+        assert!(file.write()?);
+        let mut file = RPMPackageFile::open(&path)?;
+        file.sign(&signer)?;
+        assert!(file.write()?);
+        let mut file = RPMPackageFile::open(&path)?;
+        file.delete_signatures();
+        file.sign(&ecc_signer)?;
+        assert!(file.write()?);
+        assert_eq!(len, std::fs::metadata(&path)?.len());
+        let signed = read(&path)?;
+        assert_eq!(header_and_payload(&pkg)?, header_and_payload(&signed)?);
+        signed.verify_signature(&keyring)?;
+    }
+
+    // without reserved space, the file is copied
+    let path = dir.join("copied.rpm");
+    let mut signed = read(&dir.join("signed.rpm"))?;
+    signed.metadata.signature = Header::from_entries(
+        signed
+            .metadata
+            .signature
+            .index_entries
+            .into_iter()
+            .filter(|entry| {
+                entry.tag != IndexSignatureTag::HEADER_SIGNATURES
+                    && entry.tag != IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE
+            })
+            .collect(),
+        IndexSignatureTag::HEADER_SIGNATURES,
+    );
+    signed.write(&mut std::fs::File::create(&path)?)?;
+    let mut file = RPMPackageFile::open(&path)?;
+    file.add_signature(&ecc_signer, signature::SignatureMode::default())?;
+    assert!(!file.write()?);
+    let pkg = read(&path)?;
+    assert_eq!(header_and_payload(&signed)?, header_and_payload(&pkg)?);
+    assert_eq!(
+        VerificationStatus::Verified,
+        pkg.verification_report(&keyring)?.status()
+    );
+
+    // the copy got reserved space itself
+    file.delete_signatures();
+    file.sign(&ecc_signer)?;
+    assert!(file.write()?);
+    read(&path)?.verify_signature(&keyring)?;
+
+    Ok(())
+}
+
+#[cfg(all(feature = "signature-pgp", feature = "signature-ima"))]
+#[test]
+fn test_ima_file_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::ima::{ImaSigner, ImaVerifier};
+
+    let [(rsa_key, rsa_cert), (ecdsa_key, ecdsa_cert)] = signature::x509::test::load_keys();
+    let (signing_key, _) = crate::signature::pgp::test::load_asc_keys();
+    let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?;
+
+    for (key, cert, other_cert) in [
+        (&rsa_key, &rsa_cert, &ecdsa_cert),
+        (&ecdsa_key, &ecdsa_cert, &rsa_cert),
+    ] {
+        let mut pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .with_file(
+                "./test_assets/awesome.py",
+                RPMFileOptions::new("/usr/bin/awesome").mode(0o100755),
+            )?
+            .sign_files(ImaSigner::load_from_pem_bytes(key)?)
+            .build()?;
+
+        let verifier = ImaVerifier::load_from_certificate(cert)?;
+        let signatures = pkg.metadata.signature.get_file_ima_signatures()?;
+        assert_eq!(2, signatures.len());
+        assert_eq!(
+            signatures.iter().map(|s| s.len() as i32 / 2).max(),
+            Some(pkg.metadata.signature.get_file_ima_signature_length()?)
+        );
+        assert!(signatures
+            .iter()
+            .all(|s| s.starts_with(&format!("030204{}", verifier.key_id()))));
+        pkg.verify_file_signatures(&verifier)?;
+
+        let other_verifier = ImaVerifier::load_from_certificate(other_cert)?;
+        match pkg.verify_file_signatures(&other_verifier) {
+            Err(RPMError::FileSignatureError { source, .. }) => {
+                assert!(matches!(*source, RPMError::KeyNotFoundError { .. }))
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // signing the package keeps the file signatures
+        pkg.sign(&signer)?;
+        let mut buf = Vec::new();
+        pkg.write(&mut buf)?;
+        let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+        pkg.verify_file_signatures(&verifier)?;
+    }
+
+    // packages without file signatures
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .build_and_sign(&signer)?;
+    assert!(pkg
+        .verify_file_signatures(&ImaVerifier::load_from_certificate(&rsa_cert)?)
+        .is_err());
+
+    Ok(())
+}
+
+#[cfg(all(feature = "signature-pgp", feature = "signature-verity"))]
+#[test]
+fn test_verity_file_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::verity::{VeritySigner, VerityVerifier};
+
+    let [(rsa_key, rsa_cert), (ecdsa_key, ecdsa_cert)] = signature::x509::test::load_keys();
+    let (signing_key, _) = crate::signature::pgp::test::load_asc_keys();
+    let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?;
+
+    for (key, cert, other_cert, compressor) in [
+        (&rsa_key, &rsa_cert, &ecdsa_cert, "gzip"),
+        (&ecdsa_key, &ecdsa_cert, &rsa_cert, "zstd"),
+        (&rsa_key, &rsa_cert, &ecdsa_cert, "none"),
+    ] {
+        let mut pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+            .compression(compressor.parse()?)
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .with_file(
+                "./test_assets/awesome.py",
+                RPMFileOptions::new("/usr/bin/awesome").mode(0o100755),
+            )?
+            .sign_files_verity(VeritySigner::load_from_pem(key, cert)?)
+            .build()?;
+
+        assert_eq!(
+            2,
+            pkg.metadata.signature.get_file_verity_signatures()?.len()
+        );
+        assert_eq!(
+            FS_VERITY_HASH_ALG_SHA256,
+            pkg.metadata
+                .signature
+                .get_file_verity_signature_algorithm()?
+        );
+        let verifier = VerityVerifier::load_from_certificate(cert)?;
+        pkg.verify_verity_signatures(&verifier)?;
+
+        let other_verifier = VerityVerifier::load_from_certificate(other_cert)?;
+        match pkg.verify_verity_signatures(&other_verifier) {
+            Err(RPMError::FileSignatureError { source, .. }) => {
+                assert!(matches!(*source, RPMError::KeyNotFoundError { .. }))
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // signing the package keeps the file signatures
+        pkg.sign(&signer)?;
+        let mut buf = Vec::new();
+        pkg.write(&mut buf)?;
+        let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+        pkg.verify_verity_signatures(&verifier)?;
+    }
+
+    // packages with large files use the stripped cpio format
+    let mut builder = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .compression("gzip".parse()?)
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome").mode(0o100755),
+        )?
+        .sign_files_verity(VeritySigner::load_from_pem(&rsa_key, &rsa_cert)?);
+    builder.large_file_threshold = 0;
+    let pkg = builder.build()?;
+    pkg.verify_verity_signatures(&VerityVerifier::load_from_certificate(&rsa_cert)?)?;
+    match pkg.verify_verity_signatures(&VerityVerifier::load_from_certificate(&ecdsa_cert)?) {
+        Err(RPMError::FileSignatureError { path, source }) => {
+            assert_eq!("/etc/awesome/config.toml", path);
+            assert!(matches!(*source, RPMError::KeyNotFoundError { .. }))
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // packages without file signatures
+    let pkg = RPMBuilder::new("test", "1.0.0", "MIT", "noarch", "some package")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .build_and_sign(&signer)?;
+    assert!(pkg
+        .verify_verity_signatures(&VerityVerifier::load_from_certificate(&rsa_cert)?)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_source_package() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the sources")
+        .source_package()
+        .with_spec_file("./test_assets/awesome.toml")?
+        .with_source("./test_assets/awesome.py")?
+        .no_source("vendor.tar.gz")
+        .with_patch("./test_assets/awesome.xml")?
+        .build_requires(Dependency::greater_eq("python3-devel", "3.6"))
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    assert!(pkg.metadata.lead.is_source_package());
+    assert!(header.is_source_package());
+    assert_eq!(
+        vec!["awesome.py".to_string(), "vendor.tar.gz".to_string()],
+        header.get_sources()?
+    );
+    assert_eq!(vec!["awesome.xml".to_string()], header.get_patches()?);
+    assert_eq!(
+        vec![1],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_NOSOURCE)?
+    );
+    assert!(header.get_source_rpm().is_err());
+
+    let entries = header.get_file_entries()?;
+    let paths: Vec<_> = entries.iter().map(|e| e.path.clone()).collect();
+    assert_eq!(
+        vec![
+            std::path::PathBuf::from("awesome.py"),
+            std::path::PathBuf::from("awesome.toml"),
+            std::path::PathBuf::from("awesome.xml"),
+        ],
+        paths
+    );
+    assert_eq!(
+        vec![0, RPMFILE_SPECFILE, 0],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_FILEFLAGS)?
+    );
+
+    let requires = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    assert!(requires.contains(&"python3-devel".to_string()));
+    assert!(!requires.contains(&"/bin/sh".to_string()));
+
+    let binary = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the binaries")
+        .release("2.el9")
+        .build()?;
+    assert!(!binary.metadata.lead.is_source_package());
+    assert!(!binary.metadata.header.is_source_package());
+    assert_eq!(
+        "awesome-1.0.0-2.el9.src.rpm",
+        binary.metadata.header.get_source_rpm()?
+    );
+
+    let result = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the binaries")
+        .with_source("./test_assets/awesome.py")?
+        .build();
+    assert!(matches!(result, Err(RPMError::SourcePackageOnly(_))));
+
+    let result = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the binaries")
+        .with_spec_file("./test_assets/awesome.toml")?
+        .build();
+    assert!(matches!(result, Err(RPMError::SourcePackageOnly(_))));
+
+    let result = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the sources")
+        .source_package()
+        .with_spec_file("./test_assets/awesome.toml")?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome"),
+        )?
+        .build();
+    assert!(matches!(result, Err(RPMError::BinaryPackageOnly(_))));
+
+    let result = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the sources")
+        .source_package()
+        .with_source("./test_assets/awesome.py")?
+        .with_source("./test_assets/awesome.py");
+    assert!(matches!(result, Err(RPMError::DuplicateSourceFile(_))));
+
+    let result = RPMBuilder::new("awesome", "1.0.0", "MIT", "noarch", "the sources")
+        .source_package()
+        .no_source("vendor.tar.gz")
+        .no_patch("vendor.tar.gz")
+        .build();
+    assert!(matches!(result, Err(RPMError::DuplicateSourceFile(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_relocatable_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("agent", "1.0.0", "MIT", "noarch", "relocatable agent")
+        .prefix("/opt/agent/")
+        .prefix("/etc/agent")
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/opt/agent/bin/agent"),
+        )?
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/agent/agent.toml"),
+        )?
+        .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    assert_eq!(
+        vec!["/opt/agent".to_string(), "/etc/agent".to_string()],
+        pkg.metadata.header.get_prefixes()?
+    );
+
+    let result = RPMBuilder::new("agent", "1.0.0", "MIT", "noarch", "relocatable agent")
+        .prefix("/opt/agent")
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/opt/agent-tools/bin/agent"),
+        )?
+        .build();
+    assert!(matches!(
+        result,
+        Err(RPMError::InvalidDestinationPath { .. })
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "dependency-generator")]
+#[test]
+fn test_generate_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new(
+        "awesome",
+        "1.0.0",
+        "MIT",
+        "x86_64",
+        "generated dependencies",
+    )
+    .generate_dependencies()
+    .with_file(
+        "./test_assets/awesome.toml",
+        RPMFileOptions::new("/etc/awesome/config.toml"),
+    )?
+    .with_file(
+        "./test_assets/awesome.py",
+        RPMFileOptions::new("/usr/bin/awesome").mode(0o100_755),
+    )?
+    .build()?;
+
+    let mut buf = Vec::new();
+    pkg.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    let names = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+    let flags = header.get_entry_i32_array_data(IndexTag::RPMTAG_REQUIREFLAGS)?;
+    let index = names
+        .iter()
+        .position(|name| name == "/usr/bin/env")
+        .expect("interpreter is required");
+    assert_eq!(RPMSENSE_FIND_REQUIRES as i32, flags[index]);
+
+    // files are ordered by path, the script comes second
+    assert_eq!(
+        vec![0, 0],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_FILEDEPENDSX)?
+    );
+    assert_eq!(
+        vec![0, 1],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_FILEDEPENDSN)?
+    );
+    assert_eq!(
+        vec![((b'R' as u32) << 24 | index as u32) as i32],
+        header.get_entry_i32_array_data(IndexTag::RPMTAG_DEPENDSDICT)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_file_colors_and_classes() -> Result<(), Box<dyn std::error::Error>> {
+    // a bare 64-bit little endian ELF header of a shared object
+    let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+    elf.resize(16, 0);
+    elf.extend_from_slice(&3u16.to_le_bytes());
+    let elf_path = std::env::temp_dir().join(format!("libawesome-{}.so", std::process::id()));
+    std::fs::write(&elf_path, &elf)?;
+
+    let pkg = RPMBuilder::new("awesome", "1.0.0", "MIT", "x86_64", "colored files")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome").mode(0o100_755),
+        )?
+        .with_file(
+            &elf_path,
+            RPMFileOptions::new("/usr/lib64/libawesome.so.1").mode(0o100_755),
+        )?
+        .build();
+    std::fs::remove_file(&elf_path)?;
+
+    let mut buf = Vec::new();
+    pkg?.write(&mut buf)?;
+    let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+    let header = &pkg.metadata.header;
+
+    assert_eq!(
+        vec![
+            "ASCII text",
+            "ELF 64-bit LSB shared object",
+            "a python3 script, ASCII text executable",
+        ],
+        header.get_entry_string_array_data(IndexTag::RPMTAG_CLASSDICT)?
+    );
+    let entries = header.get_file_entries()?;
+    let colors_and_classes = entries
+        .iter()
+        .map(|entry| (entry.color, entry.class.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (RPMFC_BLACK, "ASCII text"),
+            (RPMFC_BLACK, "a python3 script, ASCII text executable"),
+            (RPMFC_ELF64, "ELF 64-bit LSB shared object"),
+        ],
+        colors_and_classes
+    );
+    Ok(())
+}
+
+#[test]
+fn test_package_sizes() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = RPMBuilder::new("awesome", "1.0.0", "MIT", "x86_64", "sized")
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome").mode(0o100_755),
+        )?
+        .build()?;
+
+    let mut header = Vec::new();
+    pkg.metadata.header.write(&mut header)?;
+    let signature = &pkg.metadata.signature;
+    assert_eq!(
+        (header.len() + pkg.content.len()) as u64,
+        signature.get_size()?
+    );
+    // without compression the payload is the plain cpio archive
+    assert_eq!(pkg.content.len() as u64, signature.get_payload_size()?);
+
+    let file_sizes = std::fs::metadata("./test_assets/awesome.toml")?.len()
+        + std::fs::metadata("./test_assets/awesome.py")?.len();
+    assert_eq!(file_sizes, pkg.metadata.header.get_installed_size()?);
+    assert!(pkg
+        .metadata
+        .header
+        .get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)
+        .is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_file_digest_algorithm() -> Result<(), Box<dyn std::error::Error>> {
+    let content = std::fs::read("./test_assets/awesome.toml")?;
+    for (algorithm, expected) in [
+        (FileDigestAlgorithm::Md5, {
+            use md5::Digest;
+            md5::Md5::digest(&content).to_vec()
+        }),
+        (FileDigestAlgorithm::Sha2_512, {
+            use sha2::Digest;
+            sha2::Sha512::digest(&content).to_vec()
+        }),
+    ] {
+        let pkg = RPMBuilder::new("awesome", "1.0.0", "MIT", "x86_64", "digests")
+            .file_digest_algorithm(algorithm)
+            .with_file(
+                "./test_assets/awesome.toml",
+                RPMFileOptions::new("/etc/awesome/config.toml"),
+            )?
+            .build()?;
+
+        let mut buf = Vec::new();
+        pkg.write(&mut buf)?;
+        let pkg = RPMPackage::parse(&mut buf.as_slice())?;
+        let header = &pkg.metadata.header;
+
+        assert_eq!(algorithm, header.get_file_digest_algorithm()?);
+        let digest = header.get_file_entries()?[0].digest.clone().unwrap();
+        assert_eq!(algorithm, digest.algorithm());
+        assert_eq!(
+            FileDigest::load_from_str(algorithm, hex::encode(expected))?,
+            digest
+        );
+
+        // MD5 is what rpm assumes without the rpmlib requirement
+        let requires = header.get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?;
+        assert_eq!(
+            algorithm != FileDigestAlgorithm::Md5,
+            requires.iter().any(|name| name == "rpmlib(FileDigests)")
+        );
+    }
+
+    let result = RPMBuilder::new("awesome", "1.0.0", "MIT", "x86_64", "digests")
+        .file_digest_algorithm(FileDigestAlgorithm::Tiger192)
+        .with_file(
+            "./test_assets/awesome.toml",
+            RPMFileOptions::new("/etc/awesome/config.toml"),
+        )?
+        .build();
+    assert!(matches!(
+        result,
+        Err(RPMError::UnsupportedFileDigestAlgorithm(
+            FileDigestAlgorithm::Tiger192
+        ))
+    ));
+    Ok(())
+}